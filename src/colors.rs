@@ -18,7 +18,7 @@ mod basic_colors {
     pub const WHITE: Color = [1.0; 4];
     pub const TRANSPARENT: Color = [0.0; 4];
 }
-mod colors {
+mod extra_colors {
     use super::basic_colors::Color;
 
     pub const LIGHT_GREY: Color = [0.8, 0.8, 0.8, 1.0];
@@ -26,5 +26,5 @@ mod colors {
     pub const LIGHT_TRANSPARENT_BLUE: Color = [107.0 / 255.0, 243.0 / 255.0, 243.0 / 255.0, 0.4];
 }
 pub use basic_colors::*;
-pub use colors::*;
+pub use extra_colors::*;
 use palette::FromColor;
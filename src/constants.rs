@@ -1,12 +1,31 @@
-use crate::vec2::F64x2;
+use crate::{vec2::F64x2, world::material::PhysicsMaterial};
 
 pub const METERS_TO_POINTS: f64 = 100.0;
 pub const POINTS_TO_METERS: f64 = 1.0 / METERS_TO_POINTS;
 
 pub const GRAMS_TO_KG: f64 = 0.001;
 
+/// seconds simulated by every physics step, no matter how fast the game runs
+pub const PHYSICS_DT: f64 = 1.0 / 100.0;
+/// most physics steps to run per update, so a long hitch does not freeze the game while it catches up
+pub const MAX_PHYSICS_STEPS: u32 = 25;
+
 pub const GRAVITY: F64x2 = F64x2::new(0.0, -9.80665);
+/// N*s^2/m^2, gives a falling lemon a terminal velocity of about 10.5 m/s
+pub const DEFAULT_QUADRATIC_DRAG: f64 = 0.01;
 // pub const BOUNCE_COEFF: f64 = 0.1;
+/// m/s, bounces slower than this stop dead instead, so resting bodies do not jitter
+pub const REST_SPEED: f64 = 0.3;
+/// m/s, bodies moving slower than this along a surface can be held in place by static friction
+pub const STATIC_FRICTION_SPEED: f64 = 0.01;
+/// how much the player resists rolling, see [`crate::physics::body::RigidBody::rolling_friction`]
+pub const LEMON_ROLLING_FRICTION: f64 = 0.05;
+/// what the player is made of
+pub const LEMON_MATERIAL: PhysicsMaterial = PhysicsMaterial {
+    static_friction: 1.0,
+    kinetic_friction: 0.9,
+    restitution: 0.1,
+};
 // /// friciton coefficients
 // /// for this section, see https://en.wikipedia.org/wiki/Friction#Approximate_coefficients_of_friction
 
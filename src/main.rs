@@ -1,6 +1,8 @@
 pub mod colors;
 pub mod constants;
+pub mod physics;
 pub mod player;
+pub mod timestep;
 pub mod utils;
 pub mod vec2;
 pub mod world;
@@ -11,6 +13,8 @@ extern crate log;
 extern crate derivative;
 
 
+use std::time::Instant;
+
 use anyhow::Result;
 use glutin_window::GlutinWindow;
 use opengl_graphics::{GlGraphics, OpenGL};
@@ -22,9 +26,11 @@ use piston::{
 
 use colors::*;
 use constants::*;
+use physics::{body::RigidBody, render::draw_body, PhysicsWorld};
 use player::Player;
+use timestep::FixedTimestep;
 use vec2::F64x2;
-use world::{WorldMap, TileEffect};
+use world::WorldMap;
 
 fn main() -> Result<()> {
     pretty_env_logger::formatted_builder()
@@ -49,22 +55,34 @@ fn main() -> Result<()> {
         .unwrap();
 
     let mut gl = GlGraphics::new(opengl);
-    let mut win_size = [0f64; 2];
 
+    let mut map = WorldMap::load("assets/maps/limeon_test_map_3_100x100.png")?;
+    info!("Loaded map {:?}", map.meta.name);
+
+    let mut physics = PhysicsWorld::new();
     let mut player = Player::new(
-        F64x2::splat(1.0),
+        &mut physics,
+        map.spawn_loc(),
         113.0 * GRAMS_TO_KG, /* about how much a large lemon weighs */
         5.0,
         2.0,
     );
 
-    let mut map = WorldMap::from_path("assets/maps/limeon_test_map_3_100x100.png", &player)?;
+    for marker in map.markers.iter().filter(|marker| marker.kind == "crate") {
+        physics.add(RigidBody::new(marker.loc, 0.2, F64x2::splat(0.4)));
+    }
+
+    physics.add_map_platforms(&map);
+
+    let mut timestep = FixedTimestep::new(PHYSICS_DT, MAX_PHYSICS_STEPS);
+    // piston hands every update the same dt no matter how late it is, so the timestep is fed the real time instead
+    let mut last_update = Instant::now();
 
     let mut events = Events::new({
         let mut es = EventSettings::new();
         // rendering only when receiving input
         es.lazy = false;
-        // agressivly skips updates to catch up
+        // skips updates piston is late for, the fixed timestep catches up on the time they would have covered
         es.ups_reset = 0;
         // 100 updates per second
         es.ups = 100;
@@ -75,49 +93,73 @@ fn main() -> Result<()> {
         if let Some(args) = e.render_args() {
             use graphics::*;
 
-            win_size = args.window_size;
+            let win_size = args.window_size;
+
+            // draw between the last two physics steps, so movement looks smooth at any frame rate
+            let alpha = timestep.alpha_after(last_update.elapsed().as_secs_f64());
+            let player_loc = player.interpolated_loc(&physics, alpha);
+            let player_size = physics[player.body].size;
+            map.cam_loc = F64x2 {
+                x: player_loc.x - win_size[0] * POINTS_TO_METERS / 2.0 + player_size.x / 2.0,
+                y: player_loc.y - win_size[1] * POINTS_TO_METERS / 2.0 + player_size.y / 2.0,
+            };
 
             gl.draw(args.viewport(), |c, gl| {
-                clear(rgba(128, 204, 204, 1.0), gl);
+                clear(map.meta.clear_color, gl);
 
                 map.render(&c, gl, win_size);
-                player.draw(&c, gl, win_size[1], &map);
+                for (id, body) in physics.bodies() {
+                    if id != player.body {
+                        let color = if body.flags.kinematic {
+                            DARK_GREY
+                        } else {
+                            LIGHT_GREY
+                        };
+                        draw_body(body, color, &c, gl, win_size[1], map.cam_loc, alpha);
+                    }
+                }
+                player.draw(&c, gl, win_size[1], &map, &physics, alpha);
+                map.render_foreground(&c, gl, win_size);
             });
         }
 
-        if let Some(args) = e.update_args() {
-            player.update_phys(args.dt, &map);
-            map.cam_loc = F64x2 {
-                x: player.phys.loc.x - win_size[0] * POINTS_TO_METERS / 2.0
-                    + player.phys.size.x / 2.0,
-                y: player.phys.loc.y - win_size[1] * POINTS_TO_METERS / 2.0
-                    + player.phys.size.y / 2.0,
-            };
+        if e.update_args().is_some() {
+            // physics always steps by exactly PHYSICS_DT, so it does not depend on frame timing
+            let now = Instant::now();
+            let steps = timestep.advance((now - last_update).as_secs_f64());
+            last_update = now;
+            for _ in 0..steps {
+                player.before_step(PHYSICS_DT, &mut physics);
+                physics.step(PHYSICS_DT, &map);
+                player.after_step(PHYSICS_DT, &mut physics);
+            }
         }
 
         if let Some(args) = e.press_args() {
             match args {
-                Button::Mouse(mouse_btn) => match mouse_btn {
-                    _ => {}
-                },
+                Button::Mouse(_mouse_btn) => {}
                 Button::Keyboard(keyboard_btn) => match keyboard_btn {
                     Key::A => {
-                        player.phys.movement_forces += F64x2::new(-player.move_force, 0.0);
+                        physics[player.body].movement_forces += F64x2::new(-player.move_force, 0.0);
                     }
                     Key::D => {
-                        player.phys.movement_forces += F64x2::new(player.move_force, 0.0);
+                        physics[player.body].movement_forces += F64x2::new(player.move_force, 0.0);
                     }
                     Key::W => {
-                        player.phys.movement_forces += F64x2::new(0.0, player.move_force);
+                        physics[player.body].movement_forces += F64x2::new(0.0, player.move_force);
                     }
                     Key::S => {
-                        player.phys.movement_forces += F64x2::new(0.0, -player.move_force);
+                        physics[player.body].movement_forces += F64x2::new(0.0, -player.move_force);
                     }
                     Key::Space => {
                         // boing
-                        // if player.phys.down_to_earth {
-                        player.jump();
-                        // }
+                        player.jump(&mut physics);
+                    }
+                    Key::E => {
+                        player.launch(&mut physics);
+                    }
+                    Key::Q => {
+                        player.grapple(&mut physics, &map);
                     }
                     Key::Y => {
                         player.debug_phys = !player.debug_phys;
@@ -134,21 +176,25 @@ fn main() -> Result<()> {
 
         if let Some(args) = e.release_args() {
             match args {
-                Button::Mouse(mouse_btn) => match mouse_btn {
-                    _ => {}
-                },
+                Button::Mouse(_mouse_btn) => {}
                 Button::Keyboard(keyboard_btn) => match keyboard_btn {
                     Key::A => {
-                        player.phys.movement_forces -= F64x2::new(-player.move_force, 0.0);
+                        physics[player.body].movement_forces -= F64x2::new(-player.move_force, 0.0);
                     }
                     Key::D => {
-                        player.phys.movement_forces -= F64x2::new(player.move_force, 0.0);
+                        physics[player.body].movement_forces -= F64x2::new(player.move_force, 0.0);
                     }
                     Key::W => {
-                        player.phys.movement_forces -= F64x2::new(0.0, player.move_force);
+                        physics[player.body].movement_forces -= F64x2::new(0.0, player.move_force);
                     }
                     Key::S => {
-                        player.phys.movement_forces -= F64x2::new(0.0, -player.move_force);
+                        physics[player.body].movement_forces -= F64x2::new(0.0, -player.move_force);
+                    }
+                    Key::Space => {
+                        player.release_jump(&mut physics);
+                    }
+                    Key::Q => {
+                        player.release_grapple(&mut physics);
                     }
                     _ => {}
                 },
@@ -0,0 +1,830 @@
+//! a single dynamic object, moved by forces and tile effects and stopped by the map's collision tiles
+
+use super::{
+    collision::{self, SideHit},
+    contacts::{self, ActiveEffect, Side, TileContacts},
+    status::StatusEffect,
+    BodyId,
+};
+use crate::{
+    constants::*,
+    vec2::F64x2,
+    world::{material::PhysicsMaterial, TileEffect, WorldMap},
+};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HorizontalDirection {
+    Left,
+    Right,
+}
+
+pub fn max(a: f64, b: f64) -> f64 {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+
+/// what a body takes part in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BodyFlags {
+    /// falls with the map's gravity
+    pub gravity: bool,
+    /// stopped by collision tiles. bodies without this fly through the map
+    pub collides_with_map: bool,
+    /// affected by the effects of the tiles it touches, like speed boosts and wind
+    pub tile_effects: bool,
+    /// pushes and is pushed by other bodies with this flag
+    pub collides_with_bodies: bool,
+    /// moves only by its velocity, which whatever controls the body sets.
+    /// it goes through the map and other bodies cannot push it, like it was infinitely heavy
+    pub kinematic: bool,
+}
+
+impl Default for BodyFlags {
+    fn default() -> Self {
+        Self {
+            gravity: true,
+            collides_with_map: true,
+            tile_effects: true,
+            collides_with_bodies: true,
+            kinematic: false,
+        }
+    }
+}
+
+/// a kinematic body a body is against, which rubs on it like a moving collision tile
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MovingSurface {
+    pub body: BodyId,
+    /// side of the body the surface is on
+    pub side: Side,
+    pub material: PhysicsMaterial,
+    /// m/s
+    pub vel: F64x2,
+}
+
+/// shape of a body when colliding with other bodies.
+/// against the map, every body is its `size` box
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Collider {
+    /// the `size` box
+    #[default]
+    Aabb,
+    /// the largest circle that fits in the `size` box, centered in it.
+    /// circles roll, turning with friction from whatever they touch
+    Circle,
+}
+
+#[derive(Debug, Clone)]
+pub struct RigidBody {
+    /// m
+    ///
+    /// loc += vel * dt
+    pub loc: F64x2,
+    /// m, location before the last update, for interpolating between updates when drawing
+    pub prev_loc: F64x2,
+    /// newtons or 1 kg * m/s^2
+    pub force: F64x2,
+    /// m/s^2
+    ///
+    /// f=ma so accel = f/m
+    pub accel: F64x2,
+    /// m/s
+    ///
+    /// vel += accel * dt
+    pub vel: F64x2,
+    /// forces from whatever controls the body, like l/r movement for the player
+    pub movement_forces: F64x2,
+    /// kg
+    pub mass: f64,
+    /// last direction of movement. by default, this is right
+    pub last_direction: HorizontalDirection,
+    /// width, height from the bottom left corner
+    pub size: F64x2,
+    /// combined with the material of the tiles (and other bodies) the body touches
+    pub material: PhysicsMaterial,
+    pub flags: BodyFlags,
+    pub collider: Collider,
+    /// radians, counterclockwise. only [`Collider::Circle`] bodies turn
+    pub angle: f64,
+    /// radians, angle before the last update, for interpolating between updates when drawing
+    pub prev_angle: f64,
+    /// radians/s, counterclockwise
+    ///
+    /// angle += angular_vel * dt
+    pub angular_vel: f64,
+    /// rolling resistance coeff, slows down rolling by this times the normal force
+    pub rolling_friction: f64,
+    /// multiplier on horizontal movement force from [`TileEffect::HorizontalSpeedBoost`] tiles, 1 when there are none
+    pub speed_boost: f64,
+    /// strength of the [`TileEffect::LaunchEnable`] tiles the body is in, if any
+    pub launch_strength: Option<f64>,
+    /// newtons, from [`TileEffect::Wind`] tiles the body is in.
+    /// each tile's wind is weighted by how much of the body it covers
+    pub wind_force: F64x2,
+    /// newtons, from the map's air and [`TileEffect::Drag`] tiles the body is in.
+    /// each tile's drag is weighted by how much of the body it covers
+    pub drag_force: F64x2,
+    /// tiles touching the body, as of the start of the last update
+    pub contacts: TileContacts,
+    /// kinematic bodies the body was pushed against in the last step, set by [`super::PhysicsWorld::step`]
+    pub moving_surfaces: Vec<MovingSurface>,
+    /// effects of the tiles in `contacts` whose conditions are met
+    pub active_effects: Vec<ActiveEffect>,
+    /// statuses from [`TileEffect::GrantStatus`] tiles that started applying in the last update,
+    /// for whatever owns the body to take
+    pub granted_statuses: Vec<StatusEffect>,
+    /// multiplier on horizontal movement force, from statuses
+    pub status_speed_boost: f64,
+    /// multiplier on the map's gravity, from statuses
+    pub gravity_scale: f64,
+    /// what the body ran into in the last update
+    pub hits: Vec<SideHit>,
+    /// if the body is standing on something after the last update
+    pub grounded: bool,
+    /// side of the body that was against a wall at the start of the last update, if any
+    pub wall_contact: Option<Side>,
+}
+
+impl RigidBody {
+    pub fn new(loc: F64x2, mass: f64, size: F64x2) -> Self {
+        Self {
+            loc,
+            prev_loc: loc,
+            force: F64x2::zero(),
+            accel: F64x2::zero(),
+            vel: F64x2::zero(),
+            movement_forces: F64x2::zero(),
+            mass,
+            last_direction: HorizontalDirection::Right,
+            size,
+            material: PhysicsMaterial::NEUTRAL,
+            flags: BodyFlags::default(),
+            collider: Collider::default(),
+            angle: 0.0,
+            prev_angle: 0.0,
+            angular_vel: 0.0,
+            rolling_friction: 0.0,
+            speed_boost: 1.0,
+            launch_strength: None,
+            wind_force: F64x2::zero(),
+            drag_force: F64x2::zero(),
+            contacts: TileContacts::default(),
+            moving_surfaces: vec![],
+            active_effects: vec![],
+            granted_statuses: vec![],
+            status_speed_boost: 1.0,
+            gravity_scale: 1.0,
+            hits: vec![],
+            grounded: false,
+            wall_contact: None,
+        }
+    }
+
+    pub fn apply_impulse(&mut self, impulse: F64x2) {
+        self.vel += impulse * self.inv_mass();
+    }
+
+    /// moves the body by `delta` without going into the map, unless it goes through the map anyway
+    pub fn move_by(&mut self, delta: F64x2, map: &WorldMap) {
+        if self.flags.collides_with_map && !self.flags.kinematic {
+            self.loc = collision::sweep(self.loc, self.size, delta, map).0;
+        } else {
+            self.loc += delta;
+        }
+    }
+
+    /// 1 / mass, or 0 for kinematic bodies
+    pub fn inv_mass(&self) -> f64 {
+        if self.flags.kinematic {
+            0.0
+        } else {
+            1.0 / self.mass
+        }
+    }
+
+    /// m, middle of the `size` box
+    pub fn center(&self) -> F64x2 {
+        self.loc + self.size / 2.0
+    }
+
+    /// m, for [`Collider::Circle`]
+    pub fn radius(&self) -> f64 {
+        self.size.x.min(self.size.y) / 2.0
+    }
+
+    /// 1 / moment of inertia (kg*m^2) of a solid disk, or 0 for bodies that do not turn
+    pub fn inv_inertia(&self) -> f64 {
+        match self.collider {
+            Collider::Aabb => 0.0,
+            Collider::Circle => 2.0 * self.inv_mass() / self.radius().powi(2),
+        }
+    }
+
+    /// m/s, velocity of the point at `offset` from the center, including turning
+    pub fn point_vel(&self, offset: F64x2) -> F64x2 {
+        self.vel + F64x2::new(-offset.y, offset.x) * self.angular_vel
+    }
+
+    /// pushes the point at `offset` from the center, turning the body if it can
+    pub fn apply_impulse_at(&mut self, impulse: F64x2, offset: F64x2) {
+        self.apply_impulse(impulse);
+        self.angular_vel += offset.cross_2v(impulse) * self.inv_inertia();
+    }
+
+    /// 1 / how heavy the body feels when pushed at `offset` from the center along `direction`
+    pub fn inv_effective_mass(&self, offset: F64x2, direction: F64x2) -> f64 {
+        self.inv_mass() + offset.cross_2v(direction).powi(2) * self.inv_inertia()
+    }
+
+    /// radians, `alpha` of the way from the previous update to the current one
+    pub fn interpolated_angle(&self, alpha: f64) -> f64 {
+        self.prev_angle + (self.angle - self.prev_angle) * alpha
+    }
+
+    /// where to draw the body, `alpha` of the way from the previous update to the current one
+    pub fn interpolated_loc(&self, alpha: f64) -> F64x2 {
+        self.prev_loc.lerp(self.loc, alpha)
+    }
+
+    pub fn update(&mut self, dt: f64, map: &WorldMap) {
+        self.prev_loc = self.loc;
+        self.prev_angle = self.angle;
+        if self.flags.kinematic {
+            self.loc += self.vel * dt;
+            return;
+        }
+        let start_vel = self.vel;
+        self.contacts = TileContacts::find(self.loc, self.size, map);
+        let active_effects = if self.flags.tile_effects {
+            self.contacts.active_effects(map)
+        } else {
+            vec![]
+        };
+        let previous_effects = std::mem::replace(&mut self.active_effects, active_effects);
+        trace!("active effects: {:?}", self.active_effects);
+
+        // when several tiles have the same effect, the strongest one wins (or they add up, for wind)
+        let mut speed_boost = None;
+        self.launch_strength = None;
+        self.wind_force = F64x2::zero();
+        self.granted_statuses.clear();
+        let mut linear_drag = map.meta.linear_drag;
+        let mut quadratic_drag = map.meta.quadratic_drag;
+        for active in &self.active_effects {
+            match active.effect {
+                TileEffect::HorizontalSpeedBoost(multiplier) => {
+                    speed_boost = Some(speed_boost.map_or(multiplier, |b| max(b, multiplier)));
+                }
+                TileEffect::LaunchEnable(strength) => {
+                    self.launch_strength =
+                        Some(self.launch_strength.map_or(strength, |s| max(s, strength)));
+                }
+                TileEffect::Wind(force) => self.wind_force += force * active.weight,
+                TileEffect::Drag(linear, quadratic) => {
+                    linear_drag += linear * active.weight;
+                    quadratic_drag += quadratic * active.weight;
+                }
+                TileEffect::GrantStatus(ref status) => {
+                    if !previous_effects.iter().any(|previous| {
+                        previous.color == active.color && previous.effect == active.effect
+                    }) {
+                        self.granted_statuses.push(status.clone());
+                    }
+                }
+                TileEffect::Collision(..) => {}
+            }
+        }
+        self.speed_boost = speed_boost.unwrap_or(1.0);
+
+        self.drag_force = -(self.vel * linear_drag + self.vel * self.vel.length() * quadratic_drag);
+        // drag can slow the body down to a stop, but never push it backwards
+        let max_drag = self.mass * self.vel.length() / dt;
+        if self.drag_force.length() > max_drag {
+            self.drag_force = self.drag_force * (max_drag / self.drag_force.length());
+        }
+
+        let forces = self.force
+            + self.wind_force
+            + self.drag_force
+            + self.movement_forces * F64x2::new(self.speed_boost * self.status_speed_boost, 1.0);
+        self.accel = forces / self.mass;
+        if self.movement_forces.x > 0.0 {
+            self.last_direction = HorizontalDirection::Right;
+        } else if self.movement_forces.x < 0.0 {
+            self.last_direction = HorizontalDirection::Left;
+        }
+        let gravity = if self.flags.gravity {
+            map.meta.gravity * self.gravity_scale
+        } else {
+            F64x2::zero()
+        };
+        self.vel += gravity * dt;
+        self.vel += self.accel * dt;
+
+        self.wall_contact = None;
+        if !self.flags.collides_with_map {
+            self.angle += self.angular_vel * dt;
+            self.loc += self.vel * dt;
+            self.hits.clear();
+            self.grounded = false;
+            return;
+        }
+
+        // friction from every surface the body is against, depending on how hard the body is pressed into it
+        let total_force = forces + gravity * self.mass;
+        for side in [Side::Bottom, Side::Top, Side::Left, Side::Right] {
+            let tiles = contacts::tiles_on_side(self.loc, self.size, side, map)
+                .iter()
+                .filter_map(|tile| tile.collision)
+                .reduce(PhysicsMaterial::max_each)
+                .map(|material| (material, F64x2::zero()));
+            let moving = self
+                .moving_surfaces
+                .iter()
+                .filter(|surface| surface.side == side)
+                .map(|surface| (surface.material, surface.vel));
+            let surfaces = tiles.into_iter().chain(moving).collect::<Vec<_>>();
+            if !surfaces.is_empty() && matches!(side, Side::Left | Side::Right) {
+                self.wall_contact = Some(side);
+            }
+            let normal_force = total_force.dot(side.direction()).max(0.0);
+            // unit vector along the surface
+            let tangent = F64x2::new(side.direction().y.abs(), side.direction().x.abs());
+            for (material, surface_vel) in surfaces {
+                let material = material.combine(self.material);
+                if self.collider == Collider::Circle {
+                    self.roll_against(side, tangent, material, normal_force, surface_vel, dt);
+                    continue;
+                }
+                // relative to the surface, which only moves for moving surfaces
+                let speed = (self.vel - surface_vel).dot(tangent);
+                let new_speed = if (start_vel - surface_vel).dot(tangent).abs()
+                    < STATIC_FRICTION_SPEED
+                    && total_force.dot(tangent).abs() <= material.static_friction * normal_force
+                {
+                    // not pushed hard enough to start sliding
+                    0.0
+                } else {
+                    let friction = material.kinetic_friction * normal_force / self.mass * dt;
+                    if speed.abs() <= friction {
+                        0.0
+                    } else {
+                        speed - friction * speed.signum()
+                    }
+                };
+                self.vel += tangent * (new_speed - speed);
+            }
+        }
+
+        self.angle += self.angular_vel * dt;
+        let (new_loc, hits) = collision::sweep(self.loc, self.size, self.vel * dt, map);
+        self.loc = new_loc;
+        self.hits = hits;
+
+        for hit in &self.hits {
+            let restitution = hit.material.combine(self.material).restitution;
+            let impact_speed = -self.vel.dot(hit.normal);
+            if impact_speed > 0.0 {
+                let mut bounce_speed = impact_speed * restitution;
+                if bounce_speed < REST_SPEED {
+                    bounce_speed = 0.0;
+                }
+                self.vel += hit.normal * (impact_speed + bounce_speed);
+            }
+        }
+        self.grounded = self.hits.iter().any(|hit| hit.side == Side::Bottom)
+            || !contacts::tiles_on_side(self.loc, self.size, Side::Bottom, map).is_empty()
+            || self
+                .moving_surfaces
+                .iter()
+                .any(|surface| surface.side == Side::Bottom);
+
+        trace!("{:#?}", self);
+        trace!("tick");
+    }
+
+    /// friction on a circle from a surface on `side` moving at `surface_vel`, which grips the bottom
+    /// of the circle and turns it, so it rolls instead of sliding
+    fn roll_against(
+        &mut self,
+        side: Side,
+        tangent: F64x2,
+        material: PhysicsMaterial,
+        normal_force: f64,
+        surface_vel: F64x2,
+        dt: f64,
+    ) {
+        let offset = side.direction() * self.radius();
+        let slip = (self.point_vel(offset) - surface_vel).dot(tangent);
+        let stopping_impulse = -slip / self.inv_effective_mass(offset, tangent);
+        let max_static = material.static_friction * normal_force * dt;
+        let max_kinetic = material.kinetic_friction * normal_force * dt;
+        let impulse = if stopping_impulse.abs() <= max_static {
+            // grips the surface and rolls
+            stopping_impulse
+        } else {
+            stopping_impulse.clamp(-max_kinetic, max_kinetic)
+        };
+        self.apply_impulse_at(tangent * impulse, offset);
+
+        // rolling resistance slows down the roll, turning and moving alike so the circle does not start slipping
+        let speed = (self.vel - surface_vel).dot(tangent);
+        let slowdown = self.rolling_friction * normal_force / self.mass * dt;
+        if speed != 0.0 {
+            let new_speed = if speed.abs() <= slowdown {
+                0.0
+            } else {
+                speed - slowdown * speed.signum()
+            };
+            self.vel += tangent * (new_speed - speed);
+            self.angular_vel *= new_speed / speed;
+        }
+    }
+}
+
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{collections::HashMap, ops::Range};
+
+    use image::Rgba;
+
+    use super::*;
+    use crate::physics::PhysicsWorld;
+    use crate::world::{meta::MapMeta, MapImage, MapLayer, TileEffectCondition};
+
+    const FLOOR: Rgba<u8> = Rgba([255; 4]);
+    const BOOST_FLOOR: Rgba<u8> = Rgba([230, 180, 50, 255]);
+
+    /// 40x10 map with a floor along the bottom, boosted in the `boosted` columns
+    pub(crate) fn floor_map(boosted: Range<u32>) -> WorldMap {
+        let mut image = MapImage::new(40, 10);
+        for x in 0..40 {
+            image.put_pixel(x, 9, if boosted.contains(&x) { BOOST_FLOOR } else { FLOOR });
+        }
+        let mut effect_map = HashMap::new();
+        effect_map.insert(FLOOR, (vec![collision("floor")], vec![]));
+        effect_map.insert(
+            BOOST_FLOOR,
+            (
+                vec![
+                    collision("floor"),
+                    TileEffect::HorizontalSpeedBoost(2.0),
+                ],
+                vec![TileEffectCondition::StandingOn],
+            ),
+        );
+        WorldMap::from_effect_image(
+            image,
+            effect_map,
+            HashMap::from([
+                ("floor".to_string(), material(0.5, 0.5, 0.0)),
+                ("springy".to_string(), material(0.5, 0.5, 0.8)),
+                ("bouncy".to_string(), material(0.8, 0.8, 0.9)),
+                ("rough".to_string(), material(0.3, 0.3, 0.0)),
+            ]),
+            MapMeta::default(),
+        )
+    }
+
+    fn material(static_friction: f64, kinetic_friction: f64, restitution: f64) -> PhysicsMaterial {
+        PhysicsMaterial {
+            static_friction,
+            kinetic_friction,
+            restitution,
+        }
+    }
+
+    fn collision(material: &str) -> TileEffect {
+        TileEffect::Collision(material.to_string())
+    }
+
+    /// a lemon resting on the floor at `x`, pushing right
+    fn lemon(x: f64) -> RigidBody {
+        let mut phys = RigidBody::new(F64x2::new(x, 0.2), 0.113, F64x2::new(0.48, 0.64));
+        phys.material = LEMON_MATERIAL;
+        phys.movement_forces = F64x2::new(2.0, 0.0);
+        phys
+    }
+
+    #[test]
+    fn speed_boost_increases_horizontal_speed() {
+        let (normal_map, boost_map) = (floor_map(0..0), floor_map(0..40));
+        let (mut normal, mut boosted) = (lemon(1.0), lemon(1.0));
+        for _ in 0..30 {
+            normal.update(0.01, &normal_map);
+            boosted.update(0.01, &boost_map);
+        }
+        assert_eq!(normal.speed_boost, 1.0);
+        assert_eq!(boosted.speed_boost, 2.0);
+        assert!(
+            boosted.vel.x > normal.vel.x * 1.5,
+            "boosted: {}, normal: {}",
+            boosted.vel.x,
+            normal.vel.x
+        );
+    }
+
+    #[test]
+    fn speed_boost_ends_when_leaving_the_tile() {
+        // boost tiles end at x = 2m
+        let map = floor_map(0..10);
+        let mut phys = lemon(1.0);
+        phys.update(0.01, &map);
+        assert_eq!(phys.speed_boost, 2.0);
+
+        let mut ticks = 0;
+        while phys.loc.x < 2.0 {
+            phys.update(0.01, &map);
+            ticks += 1;
+            assert!(ticks < 1000, "never left the boost tiles");
+        }
+        // on normal floor the boost is gone, and the lemon accelerates at the normal rate again
+        phys.update(0.01, &map);
+        assert_eq!(phys.speed_boost, 1.0);
+        let vel = phys.vel.x;
+        phys.update(0.01, &map);
+        let mut reference = lemon(5.0);
+        reference.vel.x = vel;
+        reference.update(0.01, &floor_map(0..0));
+        assert!((phys.vel.x - reference.vel.x).abs() < 1e-9);
+    }
+
+    #[test]
+    fn no_speed_boost_in_the_air() {
+        let map = floor_map(0..40);
+        let mut phys = lemon(1.0);
+        phys.loc.y = 1.0;
+        phys.update(0.01, &map);
+        assert_eq!(phys.speed_boost, 1.0);
+    }
+
+    #[test]
+    fn side_conditions_activate_effects() {
+        const WALL: Rgba<u8> = Rgba([50, 222, 250, 255]);
+        let mut map = floor_map(0..0);
+        map.effect_map.insert(
+            WALL,
+            (
+                vec![
+                    collision("floor"),
+                    TileEffect::LaunchEnable(1.0),
+                ],
+                vec![TileEffectCondition::TouchingRight],
+            ),
+        );
+        // a wall two tiles high, starting at x = 2m
+        for y in 7..9 {
+            map.set_pixel(MapLayer::Effect, 10, y, WALL);
+        }
+
+        let mut phys = lemon(2.0 - 0.48);
+        phys.movement_forces = F64x2::zero();
+        phys.update(0.01, &map);
+        assert_eq!(phys.contacts.touching_right, vec![WALL]);
+        assert_eq!(phys.contacts.standing_on, vec![FLOOR]);
+        assert_eq!(
+            phys.active_effects,
+            vec![ActiveEffect {
+                color: WALL,
+                effect: TileEffect::LaunchEnable(1.0),
+                weight: 1.0,
+            }]
+        );
+        assert_eq!(phys.launch_strength, Some(1.0));
+
+        // away from the wall nothing applies
+        let mut phys = lemon(1.0);
+        phys.movement_forces = F64x2::zero();
+        phys.update(0.01, &map);
+        assert!(phys.contacts.touching_right.is_empty());
+        assert!(phys.active_effects.is_empty());
+        assert_eq!(phys.launch_strength, None);
+    }
+
+    #[test]
+    fn falling_lemon_reaches_terminal_velocity() {
+        let mut map = floor_map(0..0);
+        // tiles 100m across, so the lemon is nowhere near the floor
+        map.meta.map_px_to_meter = 100.0;
+        map.meta.linear_drag = 0.05;
+        let mut phys = lemon(1.0);
+        phys.movement_forces = F64x2::zero();
+        phys.loc.y = 900.0;
+        for _ in 0..1000 {
+            phys.update(0.01, &map);
+        }
+
+        // weight = linear * v + quadratic * v^2
+        let (linear, quadratic) = (map.meta.linear_drag, map.meta.quadratic_drag);
+        let weight = phys.mass * -map.meta.gravity.y;
+        let terminal =
+            (-linear + (linear * linear + 4.0 * quadratic * weight).sqrt()) / (2.0 * quadratic);
+        assert!(
+            (-phys.vel.y - terminal).abs() < terminal * 1e-3,
+            "fell at {} m/s, expected {} m/s",
+            -phys.vel.y,
+            terminal
+        );
+    }
+
+    #[test]
+    fn fast_lemon_does_not_tunnel_through_thin_walls() {
+        // a wall one map pixel thick, from x = 4m to 4.2m
+        let mut map = floor_map(0..0);
+        for y in 0..9 {
+            map.set_pixel(MapLayer::Effect, 20, y, FLOOR);
+        }
+        for dt in [0.01, 0.1] {
+            let mut phys = lemon(1.0);
+            phys.vel.x = 100.0;
+            for _ in 0..10 {
+                phys.update(dt, &map);
+                assert!(
+                    phys.loc.x + phys.size.x <= 4.0 + 1e-9,
+                    "went through the wall to {} with dt = {}",
+                    phys.loc.x,
+                    dt
+                );
+            }
+            assert!(phys.vel.x <= 0.0, "still moving into the wall");
+        }
+    }
+
+    #[test]
+    fn bounces_die_down_to_rest() {
+        let mut map = floor_map(0..0);
+        map.effect_map
+            .insert(FLOOR, (vec![collision("springy")], vec![]));
+        map.rebuild_grid();
+        let mut phys = lemon(1.0);
+        phys.movement_forces = F64x2::zero();
+        phys.loc.y = 1.0;
+        let mut bounced = false;
+        for _ in 0..1000 {
+            phys.update(0.01, &map);
+            bounced |= phys.vel.y > 1.0;
+        }
+        assert!(bounced, "never bounced");
+        // resting on the floor, without jittering
+        for _ in 0..10 {
+            phys.update(0.01, &map);
+            assert_eq!(phys.vel.y, 0.0);
+            assert!((phys.loc.y - 0.2).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn walls_bounce_with_their_own_factor() {
+        const BOUNCY_WALL: Rgba<u8> = Rgba([0, 255, 20, 255]);
+        let mut map = floor_map(0..0);
+        map.effect_map
+            .insert(BOUNCY_WALL, (vec![collision("bouncy")], vec![]));
+        // without drag the lemon hits the wall at exactly 10 m/s
+        map.meta.quadratic_drag = 0.0;
+        // a wall at x = 4m, hit in mid air
+        for y in 0..9 {
+            map.set_pixel(MapLayer::Effect, 20, y, BOUNCY_WALL);
+        }
+        let mut phys = lemon(4.0 - 0.48 - 0.05);
+        phys.movement_forces = F64x2::zero();
+        phys.loc.y = 1.0;
+        phys.vel = F64x2::new(10.0, 0.0);
+        phys.update(0.01, &map);
+        assert_eq!(phys.hits.len(), 1);
+        assert_eq!(phys.hits[0].side, Side::Right);
+        assert_eq!(phys.hits[0].colors, vec![BOUNCY_WALL]);
+        assert!((phys.vel.x - -9.0).abs() < 1e-9, "bounced back at {}", phys.vel.x);
+    }
+
+    #[test]
+    fn wall_friction_slows_sliding() {
+        const ROUGH_WALL: Rgba<u8> = Rgba([120, 90, 60, 255]);
+        let mut map = floor_map(0..0);
+        map.effect_map
+            .insert(ROUGH_WALL, (vec![collision("rough")], vec![]));
+        // a wall at x = 4m
+        for y in 0..9 {
+            map.set_pixel(MapLayer::Effect, 20, y, ROUGH_WALL);
+        }
+        // one lemon pushing into the wall, one just falling next to it
+        let (mut sliding, mut falling) = (lemon(4.0 - 0.48), lemon(4.0 - 0.48));
+        sliding.loc.y = 1.2;
+        falling.loc.y = 1.2;
+        falling.movement_forces = F64x2::zero();
+        for _ in 0..20 {
+            sliding.update(0.01, &map);
+            falling.update(0.01, &map);
+        }
+        assert_eq!(sliding.wall_contact, Some(Side::Right));
+        assert_eq!(falling.wall_contact, Some(Side::Right));
+        assert!(
+            falling.vel.y < sliding.vel.y && sliding.vel.y < 0.0,
+            "sliding: {}, falling: {}",
+            sliding.vel.y,
+            falling.vel.y
+        );
+    }
+
+    #[test]
+    fn static_friction_holds_until_pushed_hard_enough() {
+        let map = floor_map(0..0);
+        // static friction on the floor is 0.5 * 1.0 * 0.113kg * 9.8m/s^2, about 0.55N
+        let (mut gentle, mut hard) = (lemon(1.0), lemon(1.0));
+        gentle.movement_forces = F64x2::new(0.5, 0.0);
+        hard.movement_forces = F64x2::new(0.6, 0.0);
+        for _ in 0..100 {
+            gentle.update(0.01, &map);
+            hard.update(0.01, &map);
+        }
+        assert_eq!(gentle.loc.x, 1.0);
+        assert!(hard.loc.x > 1.0);
+    }
+
+    #[test]
+    fn grounded_only_on_the_floor() {
+        let map = floor_map(0..0);
+        let mut phys = lemon(1.0);
+        phys.update(0.01, &map);
+        assert!(phys.grounded);
+
+        phys.vel.y = 5.0;
+        phys.update(0.01, &map);
+        assert!(!phys.grounded);
+
+        let mut ticks = 0;
+        while !phys.grounded {
+            phys.update(0.01, &map);
+            ticks += 1;
+            assert!(ticks < 1000, "never landed");
+        }
+        assert!((phys.loc.y - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn flags_pick_what_a_body_takes_part_in() {
+        let map = floor_map(0..0);
+        let mut physics = PhysicsWorld::new();
+        let normal = physics.add(lemon(1.0));
+        let mut floating = lemon(3.0);
+        floating.flags.gravity = false;
+        floating.loc.y = 1.0;
+        let floating = physics.add(floating);
+        let mut ghost = lemon(5.0);
+        ghost.flags.collides_with_map = false;
+        let ghost = physics.add(ghost);
+        for _ in 0..50 {
+            physics.step(0.01, &map);
+        }
+        assert!(physics[normal].grounded);
+        assert_eq!(physics[floating].loc.y, 1.0);
+        assert!(physics[ghost].loc.y < 0.0);
+
+        // removed slots get reused
+        physics.remove(floating);
+        assert!(physics.body(floating).is_none());
+        assert_eq!(physics.add(lemon(7.0)), floating);
+    }
+
+    #[test]
+    fn circles_roll_without_slipping() {
+        let map = floor_map(0..0);
+        let mut phys = lemon(1.0);
+        phys.collider = Collider::Circle;
+        // gentle enough for the floor to grip
+        phys.movement_forces = F64x2::new(0.4, 0.0);
+        for _ in 0..100 {
+            phys.update(0.01, &map);
+        }
+        // rolling right is clockwise
+        assert!(phys.vel.x > 0.0);
+        let contact_speed = phys.point_vel(F64x2::new(0.0, -phys.radius())).x;
+        assert!(contact_speed.abs() < 1e-9, "slipping at {contact_speed}");
+        assert!(phys.angle < 0.0);
+    }
+
+    #[test]
+    fn rolling_friction_stops_rolling() {
+        let mut map = floor_map(0..0);
+        map.meta.quadratic_drag = 0.0;
+        let mut free = lemon(1.0);
+        free.movement_forces = F64x2::zero();
+        free.collider = Collider::Circle;
+        free.vel.x = 1.0;
+        free.angular_vel = -1.0 / free.radius();
+        let mut resisting = free.clone();
+        resisting.rolling_friction = 0.1;
+        for _ in 0..300 {
+            free.update(0.01, &map);
+            resisting.update(0.01, &map);
+        }
+        assert!((free.vel.x - 1.0).abs() < 1e-9);
+        assert_eq!(resisting.vel.x, 0.0);
+        assert_eq!(resisting.angular_vel, 0.0);
+    }
+}
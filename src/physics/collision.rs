@@ -0,0 +1,176 @@
+//! moving a box through the collision grid, for hitboxes of any size
+
+use image::Rgba;
+
+use super::contacts::Side;
+use crate::{
+    vec2::F64x2,
+    world::{material::PhysicsMaterial, WorldMap},
+};
+
+/// in map pixels, how far a box has to reach into a row or column of tiles for them to be in its way
+const EPSILON: f64 = 1e-6;
+
+/// solid tiles a side of the box ran into
+#[derive(Debug, Clone, PartialEq)]
+pub struct SideHit {
+    /// side of the box that hit the tiles
+    pub side: Side,
+    /// unit vector pointing away from the tiles
+    pub normal: F64x2,
+    /// colors of the tiles hit, each only once. empty when the box hit the edge of the map
+    pub colors: Vec<Rgba<u8>>,
+    /// material of the tiles hit, with the largest of each value if they differ.
+    /// the edge of the map does not bounce and has no friction
+    pub material: PhysicsMaterial,
+}
+
+impl Side {
+    /// unit vector pointing out of the box through this side
+    pub fn direction(self) -> F64x2 {
+        match self {
+            Side::Bottom => F64x2::new(0.0, -1.0),
+            Side::Top => F64x2::new(0.0, 1.0),
+            Side::Left => F64x2::new(-1.0, 0.0),
+            Side::Right => F64x2::new(1.0, 0.0),
+        }
+    }
+}
+
+/// moves the box at `loc` (bottom left corner) of `size` by `delta`, all in meters.
+///
+/// the move is split into steps of at most one tile on either axis. each step moves along x first, then along y,
+/// and stops at the first solid tiles in the way on each axis, so nothing is skipped no matter how far the box moves.
+/// the edges of the map count as solid, and tiles the box already overlaps are ignored so it can move out of them.
+/// returns where the box ended up, and what it hit (at most once per side)
+pub fn sweep(loc: F64x2, size: F64x2, delta: F64x2, map: &WorldMap) -> (F64x2, Vec<SideHit>) {
+    let mut loc = loc;
+    let mut hits: Vec<SideHit> = vec![];
+    let steps = (delta.x.abs().max(delta.y.abs()) / map.meta.map_px_to_meter)
+        .ceil()
+        .max(1.0);
+    let mut step = delta / steps;
+    for _ in 0..steps as usize {
+        let axes = [
+            (step.x, Side::Left, Side::Right),
+            (step.y, Side::Bottom, Side::Top),
+        ];
+        for (delta, backward, forward) in axes {
+            if delta == 0.0 {
+                continue;
+            }
+            let side = if delta > 0.0 { forward } else { backward };
+            let (moved, hit) = sweep_axis(loc, size, delta, side, map);
+            match side {
+                Side::Left | Side::Right => loc.x = moved,
+                Side::Bottom | Side::Top => loc.y = moved,
+            }
+            if let Some(hit) = hit {
+                // the rest of the move on this axis is blocked
+                match side {
+                    Side::Left | Side::Right => step.x = 0.0,
+                    Side::Bottom | Side::Top => step.y = 0.0,
+                }
+                hits.push(hit);
+            }
+        }
+        if step == F64x2::zero() {
+            break;
+        }
+    }
+    (loc, hits)
+}
+
+/// moves the box by `delta` (in meters, along the axis `side` faces) until `side` hits something.
+/// returns the new coordinate of the box on that axis
+fn sweep_axis(
+    loc: F64x2,
+    size: F64x2,
+    delta: f64,
+    side: Side,
+    map: &WorldMap,
+) -> (f64, Option<SideHit>) {
+    let map_px_to_meter = map.meta.map_px_to_meter;
+    let min = loc / map_px_to_meter;
+    let max = (loc + size) / map_px_to_meter;
+    let horizontal = matches!(side, Side::Left | Side::Right);
+    // position and extent along the axis of movement, the tiles the box spans across it, and where the map ends
+    let (pos, extent, across_min, across_max, limit) = if horizontal {
+        (min.x, max.x - min.x, min.y, max.y, map.grid.width() as i64)
+    } else {
+        (min.y, max.y - min.y, min.x, max.x, map.grid.height() as i64)
+    };
+    let across = (across_min + EPSILON).floor() as i64..=(across_max - EPSILON).ceil() as i64 - 1;
+    let forward = matches!(side, Side::Right | Side::Top);
+    let edge = if forward { pos + extent } else { pos };
+    let target = edge + delta / map_px_to_meter;
+
+    // the solid tiles in the row or column at `along`, if there are any
+    let blocking = |along: i64| -> Option<SideHit> {
+        let mut hit: Option<SideHit> = None;
+        for i in across.clone() {
+            let tile = if horizontal {
+                map.grid.get(along, i)
+            } else {
+                map.grid.get(i, along)
+            };
+            let (tile, material) = match tile {
+                Some(tile) => match tile.collision {
+                    Some(material) => (tile, material),
+                    None => continue,
+                },
+                None => continue,
+            };
+            let hit = hit.get_or_insert_with(|| SideHit {
+                side,
+                normal: -side.direction(),
+                colors: vec![],
+                material,
+            });
+            if !hit.colors.contains(&tile.color) {
+                hit.colors.push(tile.color);
+            }
+            hit.material = hit.material.max_each(material);
+        }
+        hit
+    };
+    let map_edge = || SideHit {
+        side,
+        normal: -side.direction(),
+        colors: vec![],
+        material: PhysicsMaterial::default(),
+    };
+
+    // walk over the tile boundaries the edge crosses, checking the tiles behind each one
+    let (stop, hit) = if forward {
+        let mut line = (edge - EPSILON).ceil() as i64;
+        loop {
+            if line as f64 >= target {
+                break (target, None);
+            }
+            if line >= limit {
+                break (line as f64, Some(map_edge()));
+            }
+            if let Some(hit) = blocking(line) {
+                break (line as f64, Some(hit));
+            }
+            line += 1;
+        }
+    } else {
+        let mut line = (edge + EPSILON).floor() as i64;
+        loop {
+            if line as f64 <= target {
+                break (target, None);
+            }
+            if line <= 0 {
+                break (line as f64, Some(map_edge()));
+            }
+            if let Some(hit) = blocking(line - 1) {
+                break (line as f64, Some(hit));
+            }
+            line -= 1;
+        }
+    };
+    let pos = if forward { stop - extent } else { stop };
+    (pos * map_px_to_meter, hit)
+}
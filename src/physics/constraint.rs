@@ -0,0 +1,194 @@
+//! springs and ropes, tying a body to a point in the world or to another body
+
+use super::{body::RigidBody, BodyId};
+use crate::{vec2::F64x2, world::WorldMap};
+
+/// what the other end of a [`Constraint`] is tied to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Anchor {
+    /// m, a fixed point in the world
+    Point(F64x2),
+    /// the center of another body
+    Body(BodyId),
+}
+
+/// pulls the center of `body` towards `anchor`, and keeps it from getting further away than `max_length`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constraint {
+    pub body: BodyId,
+    pub anchor: Anchor,
+    /// m, the length the spring pulls towards
+    pub rest_length: f64,
+    /// N/m, 0 for a rope that only pulls when stretched to `max_length`
+    pub stiffness: f64,
+    /// N*s/m, slows down stretching and shrinking
+    pub damping: f64,
+    /// m, the furthest the ends can get apart, if there is a limit
+    pub max_length: Option<f64>,
+}
+
+impl Constraint {
+    /// a rope of `length`, slack until pulled tight
+    pub fn rope(body: BodyId, anchor: Anchor, length: f64) -> Self {
+        Self {
+            body,
+            anchor,
+            rest_length: length,
+            stiffness: 0.0,
+            damping: 0.0,
+            max_length: Some(length),
+        }
+    }
+
+    pub fn spring(
+        body: BodyId,
+        anchor: Anchor,
+        rest_length: f64,
+        stiffness: f64,
+        damping: f64,
+    ) -> Self {
+        Self {
+            body,
+            anchor,
+            rest_length,
+            stiffness,
+            damping,
+            max_length: None,
+        }
+    }
+
+    /// pushes both ends by the spring's force over the next `dt` seconds
+    pub fn apply_spring(&self, body: &mut RigidBody, other: Option<&mut RigidBody>, dt: f64) {
+        if self.stiffness == 0.0 && self.damping == 0.0 {
+            return;
+        }
+        let (normal, length, stretch_speed) = match self.measure(body, other.as_deref()) {
+            Some(measured) => measured,
+            None => return,
+        };
+        // pulling the body towards the anchor when stretched
+        let pull = self.stiffness * (length - self.rest_length) + self.damping * stretch_speed;
+        body.apply_impulse(normal * pull * dt);
+        if let Some(other) = other {
+            other.apply_impulse(-normal * pull * dt);
+        }
+    }
+
+    /// pulls both ends back within `max_length`, and stops them from moving further apart
+    pub fn apply_limit(&self, body: &mut RigidBody, other: Option<&mut RigidBody>, map: &WorldMap) {
+        let max_length = match self.max_length {
+            Some(max_length) => max_length,
+            None => return,
+        };
+        let (normal, length, stretch_speed) = match self.measure(body, other.as_deref()) {
+            Some(measured) => measured,
+            None => return,
+        };
+        if length <= max_length {
+            return;
+        }
+        let inv_mass_sum = body.inv_mass() + other.as_ref().map_or(0.0, |other| other.inv_mass());
+        if inv_mass_sum == 0.0 {
+            return;
+        }
+        let correction = length - max_length;
+        body.move_by(normal * correction * body.inv_mass() / inv_mass_sum, map);
+        if stretch_speed > 0.0 {
+            body.apply_impulse(normal * stretch_speed / inv_mass_sum);
+        }
+        if let Some(other) = other {
+            other.move_by(-normal * correction * other.inv_mass() / inv_mass_sum, map);
+            if stretch_speed > 0.0 {
+                other.apply_impulse(-normal * stretch_speed / inv_mass_sum);
+            }
+        }
+    }
+
+    /// unit vector from the body towards the anchor, how far apart they are,
+    /// and how fast that is growing. `None` if they are in the same place
+    fn measure(&self, body: &RigidBody, other: Option<&RigidBody>) -> Option<(F64x2, f64, f64)> {
+        let (anchor_loc, anchor_vel) = match (self.anchor, other) {
+            (Anchor::Point(loc), _) => (loc, F64x2::zero()),
+            (Anchor::Body(_), Some(other)) => (other.center(), other.vel),
+            (Anchor::Body(_), None) => return None,
+        };
+        let between = anchor_loc - body.center();
+        let length = between.length();
+        let normal = between.normalized()?;
+        Some((normal, length, (anchor_vel - body.vel).dot(normal)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        physics::PhysicsWorld,
+        world::{meta::MapMeta, MapImage},
+    };
+
+    /// 8m by 8m of empty air
+    fn empty_map() -> WorldMap {
+        WorldMap::from_effect_image(
+            MapImage::new(40, 40),
+            HashMap::new(),
+            HashMap::new(),
+            MapMeta {
+                quadratic_drag: 0.0,
+                ..MapMeta::default()
+            },
+        )
+    }
+
+    /// a small body centered on `center`
+    fn ball(center: F64x2) -> RigidBody {
+        RigidBody::new(center - F64x2::splat(0.1), 0.1, F64x2::splat(0.2))
+    }
+
+    #[test]
+    fn rope_swings_without_stretching() {
+        let map = empty_map();
+        let mut physics = PhysicsWorld::new();
+        let body = physics.add(ball(F64x2::new(3.0, 6.0)));
+        let anchor = Anchor::Point(F64x2::new(5.0, 6.0));
+        physics.add_constraint(Constraint::rope(body, anchor, 2.0));
+        let mut lowest = f64::INFINITY;
+        for _ in 0..100 {
+            physics.step(0.01, &map);
+            let center = physics[body].center();
+            assert!((center - F64x2::new(5.0, 6.0)).length() < 2.0 + 1e-6);
+            lowest = lowest.min(center.y);
+        }
+        // swung down through the bottom of the arc
+        assert!(lowest < 4.05);
+        assert!(physics[body].center().x > 5.0);
+    }
+
+    #[test]
+    fn spring_sags_under_the_weight_it_holds() {
+        let map = empty_map();
+        let mut physics = PhysicsWorld::new();
+        let body = physics.add(ball(F64x2::new(4.0, 6.0)));
+        let anchor = Anchor::Point(F64x2::new(4.0, 7.0));
+        physics.add_constraint(Constraint::spring(body, anchor, 1.0, 10.0, 1.0));
+        for _ in 0..1000 {
+            physics.step(0.01, &map);
+        }
+        let sag = 0.1 * -map.meta.gravity.y / 10.0;
+        assert!((7.0 - physics[body].center().y - (1.0 + sag)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn removing_a_body_removes_its_constraints() {
+        let mut physics = PhysicsWorld::new();
+        let (a, b) = (
+            physics.add(ball(F64x2::new(1.0, 1.0))),
+            physics.add(ball(F64x2::new(2.0, 1.0))),
+        );
+        let rope = physics.add_constraint(Constraint::rope(a, Anchor::Body(b), 1.0));
+        physics.remove(b);
+        assert!(physics.constraint(rope).is_none());
+    }
+}
@@ -0,0 +1,201 @@
+//! finding the tiles touching a box, and which tile effects that activates
+
+use image::Rgba;
+
+use crate::{
+    vec2::F64x2,
+    world::{grid::TileMaterial, TileEffect, TileEffectCondition, WorldMap},
+};
+
+/// how close (as a fraction of a map pixel) a box has to be to a tile to count as touching it
+pub const CONTACT_TOLERANCE: f64 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Bottom,
+    Top,
+    Left,
+    Right,
+}
+
+/// colors of the tiles touching a box, by how they touch it. each color is only listed once per kind of contact
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TileContacts {
+    /// solid tiles directly below
+    pub standing_on: Vec<Rgba<u8>>,
+    /// tiles overlapping the box, with how much of the box they cover (0 to 1)
+    pub inside_of: Vec<(Rgba<u8>, f64)>,
+    /// solid tiles directly to the left
+    pub touching_left: Vec<Rgba<u8>>,
+    /// solid tiles directly to the right
+    pub touching_right: Vec<Rgba<u8>>,
+    /// solid tiles directly above
+    pub touching_head: Vec<Rgba<u8>>,
+}
+
+/// a tile effect that currently applies, and the tile it comes from
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActiveEffect {
+    pub color: Rgba<u8>,
+    pub effect: TileEffect,
+    /// how much of the box the tile covers, if it applies because of [`TileEffectCondition::InsideOf`].
+    /// 1 otherwise
+    pub weight: f64,
+}
+
+impl TileContacts {
+    /// finds the tiles touching the box at `loc` (bottom left corner) of `size`, in meters
+    pub fn find(loc: F64x2, size: F64x2, map: &WorldMap) -> Self {
+        let color = |tile: &TileMaterial| tile.color;
+        Self {
+            standing_on: tiles_on_side(loc, size, Side::Bottom, map)
+                .into_iter()
+                .map(color)
+                .collect(),
+            inside_of: tile_overlaps(loc, size, map)
+                .into_iter()
+                .map(|(tile, fraction)| (tile.color, fraction))
+                .collect(),
+            touching_left: tiles_on_side(loc, size, Side::Left, map)
+                .into_iter()
+                .map(color)
+                .collect(),
+            touching_right: tiles_on_side(loc, size, Side::Right, map)
+                .into_iter()
+                .map(color)
+                .collect(),
+            touching_head: tiles_on_side(loc, size, Side::Top, map)
+                .into_iter()
+                .map(color)
+                .collect(),
+        }
+    }
+
+    /// if `condition` holds for tiles of `color`
+    pub fn satisfies(&self, color: Rgba<u8>, condition: TileEffectCondition) -> bool {
+        match condition {
+            TileEffectCondition::StandingOn => self.standing_on.contains(&color),
+            TileEffectCondition::InsideOf => self.inside_of.iter().any(|(c, _)| *c == color),
+            TileEffectCondition::TouchingLeft => self.touching_left.contains(&color),
+            TileEffectCondition::TouchingRight => self.touching_right.contains(&color),
+            TileEffectCondition::TouchingHead => self.touching_head.contains(&color),
+        }
+    }
+
+    /// every color touching the box in any way, each only once
+    pub fn colors(&self) -> Vec<Rgba<u8>> {
+        let mut colors: Vec<Rgba<u8>> = vec![];
+        for color in self
+            .standing_on
+            .iter()
+            .chain(self.inside_of.iter().map(|(color, _)| color))
+            .chain(&self.touching_left)
+            .chain(&self.touching_right)
+            .chain(&self.touching_head)
+        {
+            if !colors.contains(color) {
+                colors.push(*color);
+            }
+        }
+        colors
+    }
+
+    /// the effects of every touching tile that has any of its conditions met.
+    ///
+    /// [`TileEffect::Collision`] is left out, as it does not depend on conditions
+    pub fn active_effects(&self, map: &WorldMap) -> Vec<ActiveEffect> {
+        let mut active = vec![];
+        for color in self.colors() {
+            let (effects, conditions) = match map.effect_map.get(&color) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            if !conditions
+                .iter()
+                .any(|condition| self.satisfies(color, *condition))
+            {
+                continue;
+            }
+            let weight = if conditions.contains(&TileEffectCondition::InsideOf) {
+                self.inside_of
+                    .iter()
+                    .find(|(c, _)| *c == color)
+                    .map_or(1.0, |(_, fraction)| *fraction)
+            } else {
+                1.0
+            };
+            active.extend(
+                effects
+                    .iter()
+                    .filter(|effect| !matches!(effect, TileEffect::Collision(..)))
+                    .map(|effect| ActiveEffect {
+                        color,
+                        effect: effect.clone(),
+                        weight,
+                    }),
+            );
+        }
+        active
+    }
+}
+
+/// materials of the solid tiles directly next to `side` of the box (each only once),
+/// if the box is resting against them
+pub fn tiles_on_side(loc: F64x2, size: F64x2, side: Side, map: &WorldMap) -> Vec<&TileMaterial> {
+    let map_px_to_meter = map.meta.map_px_to_meter;
+    let min = loc / map_px_to_meter;
+    let max = (loc + size) / map_px_to_meter;
+    // the edge of the box, and the range of tiles along it
+    let (edge, along_min, along_max) = match side {
+        Side::Bottom => (min.y, min.x, max.x),
+        Side::Top => (max.y, min.x, max.x),
+        Side::Left => (min.x, min.y, max.y),
+        Side::Right => (max.x, min.y, max.y),
+    };
+    if (edge - edge.round()).abs() > CONTACT_TOLERANCE {
+        return vec![];
+    }
+    // index of the row or column of tiles on the other side of the edge
+    let across = match side {
+        Side::Bottom | Side::Left => edge.round() as i64 - 1,
+        Side::Top | Side::Right => edge.round() as i64,
+    };
+    let mut tiles: Vec<&TileMaterial> = vec![];
+    let along = (along_min + CONTACT_TOLERANCE).floor() as i64
+        ..=(along_max - CONTACT_TOLERANCE).ceil() as i64 - 1;
+    for i in along {
+        let tile = match side {
+            Side::Bottom | Side::Top => map.grid.get(i, across),
+            Side::Left | Side::Right => map.grid.get(across, i),
+        };
+        if let Some(tile) = tile {
+            if tile.is_solid() && !tiles.iter().any(|other| std::ptr::eq(*other, tile)) {
+                tiles.push(tile);
+            }
+        }
+    }
+    tiles
+}
+
+/// materials of the tiles overlapping the box (each only once),
+/// with how much of the box they cover (0 to 1)
+pub fn tile_overlaps(loc: F64x2, size: F64x2, map: &WorldMap) -> Vec<(&TileMaterial, f64)> {
+    let map_px_to_meter = map.meta.map_px_to_meter;
+    let mut tiles: Vec<(&TileMaterial, f64)> = vec![];
+    for overlap in map.overlapping_tiles(loc, size) {
+        // tiles that are only touched do not count
+        let px = overlap.overlap / map_px_to_meter;
+        if px.x <= CONTACT_TOLERANCE || px.y <= CONTACT_TOLERANCE {
+            continue;
+        }
+        let fraction = overlap.overlap.x * overlap.overlap.y / (size.x * size.y);
+        match tiles
+            .iter_mut()
+            .find(|(other, _)| std::ptr::eq(*other, overlap.tile))
+        {
+            Some((_, total)) => *total += fraction,
+            None => tiles.push((overlap.tile, fraction)),
+        }
+    }
+    tiles
+}
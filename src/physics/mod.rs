@@ -0,0 +1,274 @@
+//! dynamic objects and everything needed to move them through a [`WorldMap`]
+
+pub mod body;
+pub mod collision;
+pub mod constraint;
+pub mod contacts;
+pub mod pair;
+pub mod platform;
+pub mod render;
+pub mod status;
+
+use std::ops::{Index, IndexMut};
+
+use crate::{
+    vec2::F64x2,
+    world::{material::PhysicsMaterial, meta::PlatformPath, WorldMap},
+};
+
+use body::RigidBody;
+use constraint::{Anchor, Constraint};
+use platform::Platform;
+
+/// how many times per step overlapping bodies are pushed apart, more makes stacks of bodies steadier
+const PAIR_ITERATIONS: usize = 4;
+
+/// refers to a body in a [`PhysicsWorld`]. stays valid until the body is removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BodyId(usize);
+
+/// refers to a constraint in a [`PhysicsWorld`]. stays valid until the constraint is removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConstraintId(usize);
+
+/// all dynamic objects, like the player, crates, enemies and projectiles
+#[derive(Debug, Clone, Default)]
+pub struct PhysicsWorld {
+    /// removed bodies leave an empty slot, so the ids of the others do not change
+    bodies: Vec<Option<RigidBody>>,
+    /// kinematic bodies moving along paths
+    platforms: Vec<Platform>,
+    /// like `bodies`, removed constraints leave an empty slot
+    constraints: Vec<Option<Constraint>>,
+}
+
+impl PhysicsWorld {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, body: RigidBody) -> BodyId {
+        match self.bodies.iter().position(Option::is_none) {
+            Some(index) => {
+                self.bodies[index] = Some(body);
+                BodyId(index)
+            }
+            None => {
+                self.bodies.push(Some(body));
+                BodyId(self.bodies.len() - 1)
+            }
+        }
+    }
+
+    /// removes a body, returning it if it was still there
+    pub fn remove(&mut self, id: BodyId) -> Option<RigidBody> {
+        self.platforms.retain(|platform| platform.body != id);
+        for slot in &mut self.constraints {
+            if slot.as_ref().is_some_and(|constraint| {
+                constraint.body == id || constraint.anchor == Anchor::Body(id)
+            }) {
+                *slot = None;
+            }
+        }
+        self.bodies.get_mut(id.0).and_then(Option::take)
+    }
+
+    pub fn add_constraint(&mut self, constraint: Constraint) -> ConstraintId {
+        match self.constraints.iter().position(Option::is_none) {
+            Some(index) => {
+                self.constraints[index] = Some(constraint);
+                ConstraintId(index)
+            }
+            None => {
+                self.constraints.push(Some(constraint));
+                ConstraintId(self.constraints.len() - 1)
+            }
+        }
+    }
+
+    /// removes a constraint, returning it if it was still there
+    pub fn remove_constraint(&mut self, id: ConstraintId) -> Option<Constraint> {
+        self.constraints.get_mut(id.0).and_then(Option::take)
+    }
+
+    pub fn constraint(&self, id: ConstraintId) -> Option<&Constraint> {
+        self.constraints.get(id.0).and_then(Option::as_ref)
+    }
+
+    /// where the anchor is now, if it is still there
+    pub fn anchor_loc(&self, anchor: Anchor) -> Option<F64x2> {
+        match anchor {
+            Anchor::Point(loc) => Some(loc),
+            Anchor::Body(id) => self.body(id).map(RigidBody::center),
+        }
+    }
+
+    /// adds a kinematic body following `path`
+    pub fn add_platform(&mut self, path: PlatformPath, material: PhysicsMaterial) -> BodyId {
+        let mut body = RigidBody::new(path.waypoints[0], 1.0, path.size);
+        body.flags.kinematic = true;
+        body.material = material;
+        let id = self.add(body);
+        self.platforms.push(Platform::new(id, path));
+        id
+    }
+
+    /// adds the platforms of the map's metadata
+    pub fn add_map_platforms(&mut self, map: &WorldMap) {
+        for path in &map.meta.platforms {
+            let material = map
+                .materials
+                .get(&path.material)
+                .copied()
+                .unwrap_or_else(|| {
+                    warn!("platform uses unknown physics material {:?}", path.material);
+                    PhysicsMaterial::default()
+                });
+            self.add_platform(path.clone(), material);
+        }
+    }
+
+    pub fn body(&self, id: BodyId) -> Option<&RigidBody> {
+        self.bodies.get(id.0).and_then(Option::as_ref)
+    }
+
+    pub fn body_mut(&mut self, id: BodyId) -> Option<&mut RigidBody> {
+        self.bodies.get_mut(id.0).and_then(Option::as_mut)
+    }
+
+    pub fn bodies(&self) -> impl Iterator<Item = (BodyId, &RigidBody)> {
+        self.bodies
+            .iter()
+            .enumerate()
+            .filter_map(|(index, body)| Some((BodyId(index), body.as_ref()?)))
+    }
+
+    pub fn bodies_mut(&mut self) -> impl Iterator<Item = (BodyId, &mut RigidBody)> {
+        self.bodies
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, body)| Some((BodyId(index), body.as_mut()?)))
+    }
+
+    /// moves every body forward by `dt` seconds, then resolves collisions between them
+    pub fn step(&mut self, dt: f64, map: &WorldMap) {
+        for platform in &mut self.platforms {
+            if let Some(Some(body)) = self.bodies.get_mut(platform.body.0) {
+                platform.steer(body, dt);
+            }
+        }
+        self.refresh_moving_surfaces();
+        self.for_each_constraint(|constraint, body, other| {
+            constraint.apply_spring(body, other, dt);
+        });
+        for (_, body) in self.bodies_mut() {
+            body.update(dt, map);
+            body.moving_surfaces.clear();
+        }
+        for iteration in 0..PAIR_ITERATIONS {
+            self.for_each_overlapping_pair(|a, b, manifold| {
+                if iteration == 0 {
+                    pair::update_grounded(a.1, b.1, manifold);
+                    pair::update_moving_surfaces((a.0, a.1), (b.0, b.1), manifold);
+                }
+                pair::resolve_velocity(a.1, b.1, manifold);
+                pair::separate(a.1, b.1, manifold, map);
+            });
+        }
+        self.for_each_constraint(|constraint, body, other| {
+            constraint.apply_limit(body, other, map);
+        });
+    }
+
+    /// runs `f` for every constraint, with its body and the body it is anchored to, if any
+    fn for_each_constraint(
+        &mut self,
+        mut f: impl FnMut(&Constraint, &mut RigidBody, Option<&mut RigidBody>),
+    ) {
+        for constraint in self.constraints.iter().flatten() {
+            let BodyId(i) = constraint.body;
+            match constraint.anchor {
+                Anchor::Point(_) => {
+                    if let Some(Some(body)) = self.bodies.get_mut(i) {
+                        f(constraint, body, None);
+                    }
+                }
+                Anchor::Body(BodyId(j)) => {
+                    if i == j || i.max(j) >= self.bodies.len() {
+                        continue;
+                    }
+                    let (head, tail) = self.bodies.split_at_mut(i.max(j));
+                    let (low, high) = (&mut head[i.min(j)], &mut tail[0]);
+                    let (body, other) = if i < j { (low, high) } else { (high, low) };
+                    if let (Some(body), Some(other)) = (body, other) {
+                        f(constraint, body, Some(other));
+                    }
+                }
+            }
+        }
+    }
+
+    /// updates the moving surfaces found in the last step to how the kinematic bodies move now
+    fn refresh_moving_surfaces(&mut self) {
+        let kinematic = self
+            .bodies()
+            .filter(|(_, body)| body.flags.kinematic)
+            .map(|(id, body)| (id, (body.vel, body.material)))
+            .collect::<Vec<_>>();
+        for (_, body) in self.bodies_mut() {
+            body.moving_surfaces.retain_mut(|surface| {
+                match kinematic.iter().find(|(id, _)| *id == surface.body) {
+                    Some((_, (vel, material))) => {
+                        surface.vel = *vel;
+                        surface.material = *material;
+                        true
+                    }
+                    None => false,
+                }
+            });
+        }
+    }
+
+    fn for_each_overlapping_pair(
+        &mut self,
+        mut f: impl FnMut((BodyId, &mut RigidBody), (BodyId, &mut RigidBody), pair::Manifold),
+    ) {
+        for i in 0..self.bodies.len() {
+            let (head, tail) = self.bodies.split_at_mut(i + 1);
+            let a = match &mut head[i] {
+                Some(a) if a.flags.collides_with_bodies => a,
+                _ => continue,
+            };
+            for (j, b) in tail.iter_mut().enumerate() {
+                let b = match b {
+                    Some(b) if b.flags.collides_with_bodies => b,
+                    _ => continue,
+                };
+                // nothing can push kinematic bodies, so two of them just go through each other
+                if a.flags.kinematic && b.flags.kinematic {
+                    continue;
+                }
+                if let Some(manifold) = pair::find(a, b) {
+                    f((BodyId(i), a), (BodyId(i + 1 + j), b), manifold);
+                }
+            }
+        }
+    }
+}
+
+impl Index<BodyId> for PhysicsWorld {
+    type Output = RigidBody;
+
+    /// panics if the body was removed
+    fn index(&self, id: BodyId) -> &RigidBody {
+        self.body(id)
+            .expect("body was removed from the physics world")
+    }
+}
+
+impl IndexMut<BodyId> for PhysicsWorld {
+    fn index_mut(&mut self, id: BodyId) -> &mut RigidBody {
+        self.body_mut(id)
+            .expect("body was removed from the physics world")
+    }
+}
@@ -0,0 +1,350 @@
+//! collisions between two bodies, pushing them apart by their masses
+
+use super::{
+    body::{Collider, MovingSurface, RigidBody},
+    contacts::Side,
+    BodyId,
+};
+use crate::{constants::*, vec2::F64x2, world::WorldMap};
+
+/// m, how far bodies may overlap before being pushed apart, so resting bodies keep touching
+const SLOP: f64 = 0.001;
+/// fraction of the overlap beyond [`SLOP`] removed each time a pair is separated
+const SEPARATION: f64 = 0.8;
+/// how far up (as a fraction of 1) a contact normal has to point for the body above to be standing on the other
+const STANDING_NORMAL_Y: f64 = 0.7;
+
+/// how two overlapping bodies touch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Manifold {
+    /// unit vector from the first body towards the second
+    pub normal: F64x2,
+    /// m, how far they overlap along `normal`
+    pub depth: f64,
+}
+
+/// how `a` and `b` overlap, if they do
+pub fn find(a: &RigidBody, b: &RigidBody) -> Option<Manifold> {
+    match (a.collider, b.collider) {
+        (Collider::Aabb, Collider::Aabb) => aabb_aabb(a, b),
+        (Collider::Circle, Collider::Circle) => circle_circle(a, b),
+        (Collider::Aabb, Collider::Circle) => aabb_circle(a, b),
+        (Collider::Circle, Collider::Aabb) => aabb_circle(b, a).map(|manifold| Manifold {
+            normal: -manifold.normal,
+            ..manifold
+        }),
+    }
+}
+
+fn aabb_aabb(a: &RigidBody, b: &RigidBody) -> Option<Manifold> {
+    let between = b.center() - a.center();
+    let overlap = (a.size + b.size) / 2.0 - F64x2::new(between.x.abs(), between.y.abs());
+    if overlap.x <= 0.0 || overlap.y <= 0.0 {
+        return None;
+    }
+    // out along the axis they overlap the least on
+    Some(if overlap.x < overlap.y {
+        Manifold {
+            normal: F64x2::new(between.x.signum(), 0.0),
+            depth: overlap.x,
+        }
+    } else {
+        Manifold {
+            normal: F64x2::new(0.0, between.y.signum()),
+            depth: overlap.y,
+        }
+    })
+}
+
+fn circle_circle(a: &RigidBody, b: &RigidBody) -> Option<Manifold> {
+    let distance = a
+        .center()
+        .dist_between_circles(b.center(), a.radius(), b.radius());
+    if distance >= 0.0 {
+        return None;
+    }
+    Some(Manifold {
+        normal: (b.center() - a.center())
+            .normalized()
+            .unwrap_or(F64x2::new(0.0, 1.0)),
+        depth: -distance,
+    })
+}
+
+fn aabb_circle(a: &RigidBody, b: &RigidBody) -> Option<Manifold> {
+    let center = b.center();
+    let closest = F64x2::new(
+        center.x.clamp(a.loc.x, a.loc.x + a.size.x),
+        center.y.clamp(a.loc.y, a.loc.y + a.size.y),
+    );
+    if closest != center {
+        let distance = closest.dist_between_circles(center, 0.0, b.radius());
+        if distance >= 0.0 {
+            return None;
+        }
+        return Some(Manifold {
+            normal: (center - closest).normalized()?,
+            depth: -distance,
+        });
+    }
+
+    // the circle's center is inside the box, out through the nearest side
+    let to_sides = [
+        (center.x - a.loc.x, F64x2::new(-1.0, 0.0)),
+        (a.loc.x + a.size.x - center.x, F64x2::new(1.0, 0.0)),
+        (center.y - a.loc.y, F64x2::new(0.0, -1.0)),
+        (a.loc.y + a.size.y - center.y, F64x2::new(0.0, 1.0)),
+    ];
+    let (to_side, normal) = to_sides
+        .into_iter()
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))?;
+    Some(Manifold {
+        normal,
+        depth: to_side + b.radius(),
+    })
+}
+
+/// bounces `a` and `b` off each other with an impulse split by their masses, and rubs them against each other
+pub fn resolve_velocity(a: &mut RigidBody, b: &mut RigidBody, manifold: Manifold) {
+    let inv_mass_sum = a.inv_mass() + b.inv_mass();
+    let relative_vel = b.vel - a.vel;
+    let closing_speed = -relative_vel.dot(manifold.normal);
+    if closing_speed <= 0.0 {
+        // already moving apart
+        return;
+    }
+
+    let material = a.material.combine(b.material);
+    let bounce_speed = if closing_speed * material.restitution < REST_SPEED {
+        0.0
+    } else {
+        closing_speed * material.restitution
+    };
+    let normal_impulse = (closing_speed + bounce_speed) / inv_mass_sum;
+    a.apply_impulse(-manifold.normal * normal_impulse);
+    b.apply_impulse(manifold.normal * normal_impulse);
+
+    // friction along the contact, never more than the normal impulse allows. it turns circles.
+    // kinematic bodies rub on the others as moving surfaces instead, see [`RigidBody::moving_surfaces`]
+    if a.flags.kinematic || b.flags.kinematic {
+        return;
+    }
+    let tangent = F64x2::new(-manifold.normal.y, manifold.normal.x);
+    let (offset_a, offset_b) = (
+        contact_offset(a, manifold.normal),
+        contact_offset(b, -manifold.normal),
+    );
+    let sliding_speed = (b.point_vel(offset_b) - a.point_vel(offset_a)).dot(tangent);
+    let friction_impulse = (-sliding_speed
+        / (a.inv_effective_mass(offset_a, tangent) + b.inv_effective_mass(offset_b, tangent)))
+    .clamp(
+        -material.kinetic_friction * normal_impulse,
+        material.kinetic_friction * normal_impulse,
+    );
+    a.apply_impulse_at(-tangent * friction_impulse, offset_a);
+    b.apply_impulse_at(tangent * friction_impulse, offset_b);
+}
+
+/// m, from the body's center to where it touches something in `direction`.
+/// only used for turning, so it does not matter for bodies that do not turn
+fn contact_offset(body: &RigidBody, direction: F64x2) -> F64x2 {
+    match body.collider {
+        Collider::Aabb => F64x2::zero(),
+        Collider::Circle => direction * body.radius(),
+    }
+}
+
+/// moves `a` and `b` apart, the lighter one further. neither is pushed into the map
+pub fn separate(a: &mut RigidBody, b: &mut RigidBody, manifold: Manifold, map: &WorldMap) {
+    let correction = (manifold.depth - SLOP).max(0.0) * SEPARATION;
+    if correction == 0.0 {
+        return;
+    }
+    let inv_mass_sum = a.inv_mass() + b.inv_mass();
+    a.move_by(
+        -manifold.normal * correction * a.inv_mass() / inv_mass_sum,
+        map,
+    );
+    b.move_by(
+        manifold.normal * correction * b.inv_mass() / inv_mass_sum,
+        map,
+    );
+}
+
+/// lets a body rub on a kinematic body it is against, see [`RigidBody::moving_surfaces`]
+pub fn update_moving_surfaces(
+    (a_id, a): (BodyId, &mut RigidBody),
+    (b_id, b): (BodyId, &mut RigidBody),
+    manifold: Manifold,
+) {
+    if b.flags.kinematic && !a.flags.kinematic {
+        a.moving_surfaces.push(MovingSurface {
+            body: b_id,
+            side: side_towards(manifold.normal),
+            material: b.material,
+            vel: b.vel,
+        });
+    } else if a.flags.kinematic && !b.flags.kinematic {
+        b.moving_surfaces.push(MovingSurface {
+            body: a_id,
+            side: side_towards(-manifold.normal),
+            material: a.material,
+            vel: a.vel,
+        });
+    }
+}
+
+/// side of a box facing `direction` the most
+fn side_towards(direction: F64x2) -> Side {
+    if direction.x.abs() > direction.y.abs() {
+        if direction.x > 0.0 {
+            Side::Right
+        } else {
+            Side::Left
+        }
+    } else if direction.y > 0.0 {
+        Side::Top
+    } else {
+        Side::Bottom
+    }
+}
+
+/// marks the body on top as grounded, if one is standing on the other
+pub fn update_grounded(a: &mut RigidBody, b: &mut RigidBody, manifold: Manifold) {
+    if manifold.normal.y <= -STANDING_NORMAL_Y {
+        a.grounded = true;
+    } else if manifold.normal.y >= STANDING_NORMAL_Y {
+        b.grounded = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        physics::{body, PhysicsWorld},
+        world::{
+            material::PhysicsMaterial,
+            meta::{PathMode, PlatformPath},
+        },
+    };
+
+    /// the body tests' floor map, without air drag
+    fn floor_map() -> WorldMap {
+        let mut map = body::tests::floor_map(0..0);
+        map.meta.quadratic_drag = 0.0;
+        map
+    }
+
+    fn floating(loc: F64x2, mass: f64, size: f64, vel: F64x2) -> RigidBody {
+        let mut body = RigidBody::new(loc, mass, F64x2::splat(size));
+        body.flags.gravity = false;
+        body.vel = vel;
+        body
+    }
+
+    #[test]
+    fn lemon_pushes_crate() {
+        let map = floor_map();
+        let mut physics = PhysicsWorld::new();
+        let lemon = physics.add(floating(
+            F64x2::new(1.0, 1.0),
+            0.1,
+            0.4,
+            F64x2::new(1.0, 0.0),
+        ));
+        let crate_ = physics.add(floating(F64x2::new(2.0, 1.0), 0.3, 0.4, F64x2::zero()));
+        for _ in 0..100 {
+            physics.step(0.01, &map);
+        }
+        let (lemon, crate_) = (&physics[lemon], &physics[crate_]);
+        assert!(crate_.vel.x > 0.0);
+        assert!(lemon.loc.x + lemon.size.x <= crate_.loc.x + SLOP);
+        let momentum = lemon.vel.x * lemon.mass + crate_.vel.x * crate_.mass;
+        assert!((momentum - 0.1).abs() < 1e-9, "momentum {momentum}");
+    }
+
+    #[test]
+    fn bouncy_lemons_swap_velocities() {
+        let map = floor_map();
+        let mut physics = PhysicsWorld::new();
+        let mut bouncy = |x: f64, vel: f64| {
+            let mut lemon = floating(F64x2::new(x, 1.0), 0.1, 0.4, F64x2::new(vel, 0.0));
+            lemon.collider = Collider::Circle;
+            lemon.material.restitution = 1.0;
+            physics.add(lemon)
+        };
+        let (left, right) = (bouncy(1.0, 1.0), bouncy(2.0, -0.5));
+        for _ in 0..100 {
+            physics.step(0.01, &map);
+        }
+        assert!((physics[left].vel.x + 0.5).abs() < 1e-9);
+        assert!((physics[right].vel.x - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn lemon_stands_on_crate() {
+        let map = floor_map();
+        let mut physics = PhysicsWorld::new();
+        let crate_ = physics.add(RigidBody::new(F64x2::new(1.0, 0.2), 2.0, F64x2::splat(0.4)));
+        let lemon = physics.add(RigidBody::new(F64x2::new(1.1, 1.0), 0.1, F64x2::splat(0.3)));
+        for _ in 0..200 {
+            physics.step(0.01, &map);
+        }
+        assert!(physics[lemon].grounded);
+        assert!((physics[lemon].loc.y - 0.6).abs() < 0.01);
+        assert!((physics[crate_].loc.y - 0.2).abs() < 1e-9);
+    }
+
+    fn platform(waypoints: Vec<F64x2>, speed: f64) -> PlatformPath {
+        PlatformPath {
+            size: F64x2::new(2.0, 0.2),
+            material: "floor".to_string(),
+            waypoints,
+            speed,
+            mode: PathMode::PingPong,
+        }
+    }
+
+    #[test]
+    fn moving_platform_carries_crate() {
+        let map = floor_map();
+        let mut physics = PhysicsWorld::new();
+        let path = platform(vec![F64x2::new(1.0, 1.0), F64x2::new(6.0, 1.0)], 1.0);
+        let platform = physics.add_platform(path, PhysicsMaterial::NEUTRAL);
+        let crate_ = physics.add(RigidBody::new(F64x2::new(1.5, 1.3), 0.5, F64x2::splat(0.4)));
+        let offset = |physics: &PhysicsWorld| physics[crate_].loc - physics[platform].loc;
+        for _ in 0..100 {
+            physics.step(0.01, &map);
+        }
+        let landed_offset = offset(&physics);
+        for _ in 0..100 {
+            physics.step(0.01, &map);
+        }
+        assert!(physics[crate_].grounded);
+        let vel = physics[crate_].vel.x;
+        assert!((vel - 1.0).abs() < 1e-9, "crate moving at {vel}");
+        // gripped the platform once it caught up with it
+        assert!((offset(&physics) - landed_offset).length() < 1e-6);
+        assert!((offset(&physics).y - 0.2).abs() < 0.01);
+    }
+
+    #[test]
+    fn rising_platform_lifts_lemon() {
+        let map = floor_map();
+        let mut physics = PhysicsWorld::new();
+        let path = platform(vec![F64x2::new(1.0, 0.2), F64x2::new(1.0, 3.0)], 1.0);
+        let platform = physics.add_platform(path, PhysicsMaterial::NEUTRAL);
+        let mut lemon = RigidBody::new(F64x2::new(1.5, 0.4), 0.1, F64x2::splat(0.4));
+        lemon.collider = Collider::Circle;
+        let lemon = physics.add(lemon);
+        // not long enough to reach the ceiling
+        for _ in 0..80 {
+            physics.step(0.01, &map);
+        }
+        assert!(physics[lemon].grounded);
+        let platform_top = physics[platform].loc.y + physics[platform].size.y;
+        assert!((physics[lemon].loc.y - platform_top).abs() < 0.01);
+        assert!((physics[lemon].vel.y - 1.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,117 @@
+//! moving platforms, kinematic bodies following the waypoints of a [`PlatformPath`]
+
+use super::{body::RigidBody, BodyId};
+use crate::world::meta::{PathMode, PlatformPath};
+
+#[derive(Debug, Clone)]
+pub struct Platform {
+    pub body: BodyId,
+    pub path: PlatformPath,
+    /// index of the waypoint the platform is heading for
+    next: usize,
+    /// if going through the waypoints from last to first, for [`PathMode::PingPong`]
+    backwards: bool,
+}
+
+impl Platform {
+    /// a platform for `body`, which has to be kinematic and at the first waypoint
+    pub fn new(body: BodyId, path: PlatformPath) -> Self {
+        Self {
+            body,
+            next: 1 % path.waypoints.len(),
+            path,
+            backwards: false,
+        }
+    }
+
+    /// sets the body's velocity to head for the next waypoint, so it does not overshoot it in the next `dt` seconds
+    pub fn steer(&mut self, body: &mut RigidBody, dt: f64) {
+        let to_next = self.path.waypoints[self.next] - body.loc;
+        let distance = to_next.length();
+        if distance <= self.path.speed * dt {
+            body.vel = to_next / dt;
+            self.advance();
+        } else {
+            body.vel = to_next / distance * self.path.speed;
+        }
+    }
+
+    fn advance(&mut self) {
+        let last = self.path.waypoints.len() - 1;
+        if last == 0 {
+            return;
+        }
+        match self.path.mode {
+            PathMode::Loop => self.next = (self.next + 1) % (last + 1),
+            PathMode::PingPong => {
+                if self.backwards && self.next == 0 || !self.backwards && self.next == last {
+                    self.backwards = !self.backwards;
+                }
+                if self.backwards {
+                    self.next -= 1;
+                } else {
+                    self.next += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec2::F64x2;
+
+    /// where the platform is after each second, along a 1m square
+    fn corners(mode: PathMode) -> Vec<F64x2> {
+        let path = PlatformPath {
+            size: F64x2::splat(1.0),
+            material: String::new(),
+            waypoints: vec![
+                F64x2::new(0.0, 0.0),
+                F64x2::new(1.0, 0.0),
+                F64x2::new(1.0, 1.0),
+            ],
+            speed: 1.0,
+            mode,
+        };
+        let mut body = RigidBody::new(path.waypoints[0], 1.0, path.size);
+        let mut platform = Platform::new(BodyId(0), path);
+        (0..4)
+            .map(|_| {
+                for _ in 0..100 {
+                    platform.steer(&mut body, 0.01);
+                    body.loc += body.vel * 0.01;
+                }
+                F64x2::new(body.loc.x.round(), body.loc.y.round())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ping_pong_goes_back_the_way_it_came() {
+        assert_eq!(
+            corners(PathMode::PingPong),
+            vec![
+                F64x2::new(1.0, 0.0),
+                F64x2::new(1.0, 1.0),
+                F64x2::new(1.0, 0.0),
+                F64x2::new(0.0, 0.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn loop_goes_straight_back_to_the_start() {
+        // the way back is sqrt(2)m long, so it is heading for the second waypoint again after 4s
+        assert_eq!(
+            corners(PathMode::Loop),
+            vec![
+                F64x2::new(1.0, 0.0),
+                F64x2::new(1.0, 1.0),
+                F64x2::new(0.0, 0.0),
+                F64x2::new(1.0, 0.0),
+            ]
+        );
+    }
+}
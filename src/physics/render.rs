@@ -0,0 +1,37 @@
+//! drawing bodies that do not have a sprite of their own
+
+use opengl_graphics::GlGraphics;
+
+use super::body::RigidBody;
+use crate::{colors::Color, constants::*, utils::rectangle_by_points, vec2::F64x2};
+
+/// draws the body's box, `alpha` of the way between the last two updates
+pub fn draw_body(
+    body: &RigidBody,
+    color: Color,
+    c: &graphics::Context,
+    gl: &mut GlGraphics,
+    win_height: f64,
+    cam_loc: F64x2,
+    alpha: f64,
+) {
+    use graphics::*;
+
+    let globalize_physics_cord = move |coord: F64x2| -> F64x2 {
+        F64x2 {
+            x: coord.x,
+            y: win_height - coord.y,
+        }
+    };
+    let loc = body.interpolated_loc(alpha);
+    Rectangle::new(color).draw(
+        rectangle_by_points(
+            globalize_physics_cord(loc * METERS_TO_POINTS),
+            globalize_physics_cord((loc + body.size) * METERS_TO_POINTS),
+        ),
+        &DrawState::default(),
+        c.transform
+            .trans(-cam_loc.x * METERS_TO_POINTS, cam_loc.y * METERS_TO_POINTS),
+        gl,
+    );
+}
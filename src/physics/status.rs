@@ -0,0 +1,209 @@
+//! timed effects on a body, that keep going after whatever granted them is gone
+
+use std::mem::discriminant;
+
+use serde::Deserialize;
+
+use super::body::RigidBody;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum StatusKind {
+    /// faster horizontal movement ( multiplier )
+    SpeedBoost(f64),
+    /// scales gravity, below 1 for low gravity ( multiplier )
+    GravityScale(f64),
+}
+
+impl StatusKind {
+    /// how far this is from doing nothing, used to pick the stronger of two statuses
+    pub fn strength(&self) -> f64 {
+        match *self {
+            StatusKind::SpeedBoost(multiplier) | StatusKind::GravityScale(multiplier) => {
+                (multiplier - 1.0).abs()
+            }
+        }
+    }
+
+    /// runs every tick the status is active, before the physics update
+    fn on_tick(&self, phys: &mut RigidBody) {
+        match *self {
+            StatusKind::SpeedBoost(multiplier) => phys.status_speed_boost *= multiplier,
+            StatusKind::GravityScale(multiplier) => phys.gravity_scale *= multiplier,
+        }
+    }
+
+    /// runs once, on the tick the status runs out
+    fn on_expire(&self, _phys: &mut RigidBody) {
+        debug!("status {:?} expired", self);
+    }
+}
+
+/// what happens when a status is granted while the player already has one of the same kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum Stacking {
+    /// the new status replaces the old one, restarting the timer
+    #[default]
+    Refresh,
+    /// the remaining times add up, and the new strength is used
+    Stack,
+    /// the longer remaining time and the stronger effect are kept
+    Max,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    /// seconds
+    pub duration: f64,
+    #[serde(default)]
+    pub stacking: Stacking,
+    /// seconds left. set to `duration` when granted
+    #[serde(skip)]
+    pub remaining: f64,
+}
+
+impl StatusEffect {
+    pub fn new(kind: StatusKind, duration: f64, stacking: Stacking) -> Self {
+        Self {
+            kind,
+            duration,
+            stacking,
+            remaining: duration,
+        }
+    }
+}
+
+/// the statuses a body currently has, at most one of each kind
+#[derive(Debug, Clone, Default)]
+pub struct StatusEffects {
+    pub active: Vec<StatusEffect>,
+}
+
+impl StatusEffects {
+    /// adds `status`, combining it with an active status of the same kind by the new status' [`Stacking`]
+    pub fn grant(&mut self, mut status: StatusEffect) {
+        status.remaining = status.duration;
+        let existing = match self
+            .active
+            .iter_mut()
+            .find(|other| discriminant(&other.kind) == discriminant(&status.kind))
+        {
+            Some(existing) => existing,
+            None => {
+                self.active.push(status);
+                return;
+            }
+        };
+        match status.stacking {
+            Stacking::Refresh => *existing = status,
+            Stacking::Stack => {
+                existing.kind = status.kind;
+                existing.remaining += status.duration;
+                existing.duration = existing.remaining;
+            }
+            Stacking::Max => {
+                if status.kind.strength() > existing.kind.strength() {
+                    existing.kind = status.kind;
+                }
+                existing.remaining = existing.remaining.max(status.remaining);
+                existing.duration = existing.duration.max(status.duration);
+            }
+        }
+    }
+
+    /// applies every active status to `phys` and counts them down, removing the ones that ran out
+    pub fn tick(&mut self, dt: f64, phys: &mut RigidBody) {
+        phys.status_speed_boost = 1.0;
+        phys.gravity_scale = 1.0;
+        for status in &mut self.active {
+            status.kind.on_tick(phys);
+            status.remaining -= dt;
+        }
+        self.active.retain(|status| {
+            if status.remaining > 0.0 {
+                true
+            } else {
+                status.kind.on_expire(phys);
+                false
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec2::F64x2;
+
+    /// the only status left after granting a 2x speed boost for 3s and then `second`
+    fn granted_twice(second: StatusEffect) -> StatusEffect {
+        let mut statuses = StatusEffects::default();
+        statuses.grant(StatusEffect::new(
+            StatusKind::SpeedBoost(2.0),
+            3.0,
+            Stacking::Refresh,
+        ));
+        statuses.grant(second);
+        assert_eq!(statuses.active.len(), 1);
+        statuses.active[0].clone()
+    }
+
+    #[test]
+    fn refresh_replaces_the_old_status() {
+        let status = granted_twice(StatusEffect::new(
+            StatusKind::SpeedBoost(1.5),
+            1.0,
+            Stacking::Refresh,
+        ));
+        assert_eq!(status.kind, StatusKind::SpeedBoost(1.5));
+        assert_eq!(status.remaining, 1.0);
+    }
+
+    #[test]
+    fn stack_adds_up_the_time() {
+        let status = granted_twice(StatusEffect::new(
+            StatusKind::SpeedBoost(1.5),
+            1.0,
+            Stacking::Stack,
+        ));
+        assert_eq!(status.kind, StatusKind::SpeedBoost(1.5));
+        assert_eq!(status.remaining, 4.0);
+        assert_eq!(status.duration, 4.0);
+    }
+
+    #[test]
+    fn max_keeps_the_longer_and_the_stronger() {
+        let status = granted_twice(StatusEffect::new(
+            StatusKind::SpeedBoost(1.5),
+            5.0,
+            Stacking::Max,
+        ));
+        assert_eq!(status.kind, StatusKind::SpeedBoost(2.0));
+        assert_eq!(status.remaining, 5.0);
+    }
+
+    #[test]
+    fn expired_statuses_stop_applying() {
+        let mut phys = RigidBody::new(F64x2::zero(), 1.0, F64x2::splat(1.0));
+        let mut statuses = StatusEffects::default();
+        statuses.grant(StatusEffect::new(
+            StatusKind::SpeedBoost(2.0),
+            0.015,
+            Stacking::Refresh,
+        ));
+        statuses.grant(StatusEffect::new(
+            StatusKind::GravityScale(0.5),
+            0.025,
+            Stacking::Refresh,
+        ));
+        statuses.tick(0.01, &mut phys);
+        assert_eq!((phys.status_speed_boost, phys.gravity_scale), (2.0, 0.5));
+        statuses.tick(0.01, &mut phys);
+        assert_eq!(statuses.active.len(), 1);
+        statuses.tick(0.01, &mut phys);
+        assert_eq!((phys.status_speed_boost, phys.gravity_scale), (1.0, 0.5));
+        assert!(statuses.active.is_empty());
+        statuses.tick(0.01, &mut phys);
+        assert_eq!((phys.status_speed_boost, phys.gravity_scale), (1.0, 1.0));
+    }
+}
@@ -0,0 +1,200 @@
+//! when the player can jump, and how high: coyote time, buffered jumps, wall jumps and cutting jumps short
+
+use crate::physics::body::RigidBody;
+
+#[derive(Debug, Clone)]
+pub struct JumpState {
+    // cfg values
+    /// force added to y velocity on jumping
+    pub jump_force: f64,
+    /// m/s away from the wall, added when jumping while against a wall in the air
+    pub wall_jump_push: f64,
+    /// seconds after leaving the ground during which the player can still jump
+    pub coyote_time: f64,
+    /// seconds a jump pressed in the air is remembered, so it still happens when landing shortly after
+    pub jump_buffer_time: f64,
+    /// upward velocity is multiplied by this when jump is released while still rising
+    pub jump_cut_factor: f64,
+    // state
+    /// seconds since the player was last grounded
+    pub time_since_grounded: f64,
+    /// seconds left for a buffered jump to happen
+    pub jump_buffered_for: f64,
+    /// if the player jumped since last being grounded, so coyote time does not allow a second jump
+    pub has_jumped: bool,
+    /// if the player is still rising from a jump, and releasing jump would cut it short
+    pub jump_rising: bool,
+    /// if jump is held down, so a buffered jump that happens after letting go is cut short right away
+    pub jump_held: bool,
+}
+
+impl JumpState {
+    pub fn new(jump_force: f64) -> Self {
+        Self {
+            jump_force,
+            wall_jump_push: 3.0,
+            coyote_time: 0.1,
+            jump_buffer_time: 0.1,
+            jump_cut_factor: 0.5,
+            time_since_grounded: 0.0,
+            jump_buffered_for: 0.0,
+            has_jumped: false,
+            jump_rising: false,
+            jump_held: false,
+        }
+    }
+
+    /// jumps if the player can, otherwise remembers the press for [`JumpState::jump_buffer_time`]
+    pub fn press(&mut self, phys: &mut RigidBody) {
+        self.jump_held = true;
+        self.jump_buffered_for = self.jump_buffer_time;
+        self.try_jump(phys);
+    }
+
+    /// makes the jump lower, if the player is still rising from it
+    pub fn release(&mut self, phys: &mut RigidBody) {
+        self.jump_held = false;
+        self.cut(phys);
+    }
+
+    /// keeps track of the time since leaving the ground, and runs buffered jumps, after the physics update
+    pub fn after_step(&mut self, dt: f64, phys: &mut RigidBody) {
+        if phys.grounded {
+            self.time_since_grounded = 0.0;
+            self.has_jumped = false;
+        } else {
+            self.time_since_grounded += dt;
+        }
+        if phys.vel.y <= 0.0 {
+            self.jump_rising = false;
+        }
+        if self.jump_buffered_for > 0.0 {
+            if self.try_jump(phys) {
+                // jump was tapped and let go of before the jump happened
+                if !self.jump_held {
+                    self.cut(phys);
+                }
+            } else {
+                self.jump_buffered_for = (self.jump_buffered_for - dt).max(0.0);
+            }
+        }
+    }
+
+    fn cut(&mut self, phys: &mut RigidBody) {
+        if self.jump_rising && phys.vel.y > 0.0 {
+            phys.vel.y *= self.jump_cut_factor;
+        }
+        self.jump_rising = false;
+    }
+
+    /// jumps off the ground (or shortly after leaving it), or off a wall while in the air.
+    /// returns if the player jumped
+    fn try_jump(&mut self, phys: &mut RigidBody) -> bool {
+        let from_ground =
+            phys.grounded || (!self.has_jumped && self.time_since_grounded <= self.coyote_time);
+        let wall = if from_ground { None } else { phys.wall_contact };
+        if !from_ground && wall.is_none() {
+            return false;
+        }
+
+        phys.vel.y = phys.vel.y.max(0.0) + self.jump_force;
+        // wall jump, away from the wall
+        if let Some(side) = wall {
+            phys.vel -= side.direction() * self.wall_jump_push;
+        }
+        self.has_jumped = true;
+        self.jump_rising = true;
+        self.jump_buffered_for = 0.0;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec2::F64x2;
+
+    const DT: f64 = 0.01;
+
+    fn standing() -> (JumpState, RigidBody) {
+        let mut phys = RigidBody::new(F64x2::zero(), 0.1, F64x2::splat(0.4));
+        phys.grounded = true;
+        let mut jump = JumpState::new(5.0);
+        jump.after_step(DT, &mut phys);
+        (jump, phys)
+    }
+
+    /// `seconds` of falling, without anything to stand on
+    fn fall(jump: &mut JumpState, phys: &mut RigidBody, seconds: f64) {
+        phys.grounded = false;
+        for _ in 0..(seconds / DT).round() as usize {
+            phys.vel.y = -1.0;
+            jump.after_step(DT, phys);
+        }
+    }
+
+    fn land(jump: &mut JumpState, phys: &mut RigidBody) {
+        phys.grounded = true;
+        phys.vel.y = 0.0;
+        jump.after_step(DT, phys);
+    }
+
+    #[test]
+    fn coyote_time_allows_one_late_jump() {
+        let (mut jump, mut phys) = standing();
+        fall(&mut jump, &mut phys, 0.05);
+        jump.press(&mut phys);
+        assert_eq!(phys.vel.y, 5.0);
+
+        // no second jump from coyote time after jumping
+        phys.vel.y = -1.0;
+        jump.press(&mut phys);
+        assert_eq!(phys.vel.y, -1.0);
+
+        let (mut jump, mut phys) = standing();
+        fall(&mut jump, &mut phys, 0.2);
+        jump.press(&mut phys);
+        assert_eq!(phys.vel.y, -1.0);
+    }
+
+    #[test]
+    fn jump_pressed_just_before_landing_happens_on_landing() {
+        let (mut jump, mut phys) = standing();
+        fall(&mut jump, &mut phys, 0.5);
+        jump.press(&mut phys);
+        assert_eq!(phys.vel.y, -1.0);
+        fall(&mut jump, &mut phys, 0.05);
+        land(&mut jump, &mut phys);
+        assert_eq!(phys.vel.y, 5.0);
+
+        // too early, the press is forgotten by the time the player lands
+        let (mut jump, mut phys) = standing();
+        fall(&mut jump, &mut phys, 0.5);
+        jump.press(&mut phys);
+        fall(&mut jump, &mut phys, 0.2);
+        land(&mut jump, &mut phys);
+        assert_eq!(phys.vel.y, 0.0);
+    }
+
+    #[test]
+    fn releasing_jump_cuts_it_short() {
+        let (mut jump, mut phys) = standing();
+        jump.press(&mut phys);
+        jump.release(&mut phys);
+        assert_eq!(phys.vel.y, 2.5);
+        // only once
+        jump.release(&mut phys);
+        assert_eq!(phys.vel.y, 2.5);
+    }
+
+    #[test]
+    fn buffered_jump_released_before_landing_is_cut_short() {
+        let (mut jump, mut phys) = standing();
+        fall(&mut jump, &mut phys, 0.5);
+        jump.press(&mut phys);
+        jump.release(&mut phys);
+        fall(&mut jump, &mut phys, 0.05);
+        land(&mut jump, &mut phys);
+        assert_eq!(phys.vel.y, 2.5);
+    }
+}
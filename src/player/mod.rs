@@ -1,30 +1,55 @@
-mod phys;
+pub mod jump;
 
 use image::{imageops, io::Reader as ImageReader};
 use opengl_graphics::{GlGraphics, Texture, TextureSettings};
 
 use crate::colors::*;
 use crate::constants::*;
+use crate::physics::{
+    body::{Collider, HorizontalDirection, RigidBody},
+    constraint::{Anchor, Constraint},
+    status::{StatusEffect, StatusEffects, StatusKind},
+    BodyId, ConstraintId, PhysicsWorld,
+};
 use crate::utils::rectangle_by_points;
 use crate::vec2::F64x2;
 
-use phys::{HorizontalDirection, PlayerPhys};
+use jump::JumpState;
 
 #[derive(Derivative)]
 #[derivative(Debug)]
 pub struct Player {
-    pub phys: PlayerPhys,
+    /// the player's body in the [`PhysicsWorld`]
+    pub body: BodyId,
     #[derivative(Debug = "ignore")]
     pub sprites: (Texture, Texture),
     // cfg values
-    /// force added to y velocity on jumping
-    pub jump_force: f64,
     pub move_force: f64,
+    /// N*s, multiplied by the launch tile's strength
+    pub launch_impulse: f64,
+    /// seconds between launches
+    pub launch_cooldown: f64,
+    /// m, how far away the grappling hook can catch on a collision tile
+    pub grapple_range: f64,
     pub debug_phys: bool,
+    // state
+    /// seconds until the player can launch again
+    pub launch_cooldown_left: f64,
+    pub statuses: StatusEffects,
+    pub jump_state: JumpState,
+    /// the rope of the grappling hook, while it is caught on something
+    pub grapple: Option<ConstraintId>,
 }
 
 impl Player {
-    pub fn new(loc: F64x2, mass: f64, jump_force: f64, move_force: f64) -> Self {
+    /// adds the player's body to `physics`
+    pub fn new(
+        physics: &mut PhysicsWorld,
+        loc: F64x2,
+        mass: f64,
+        jump_force: f64,
+        move_force: f64,
+    ) -> Self {
         let player_image = ImageReader::open("assets/player/cursd_le_mon_smol.png")
             .unwrap()
             .decode()
@@ -48,19 +73,30 @@ impl Player {
             Texture::from_image(&player_image_upscaled, &TextureSettings::new()),
         );
 
+        let mut body = RigidBody::new(
+            loc,
+            mass,
+            F64x2 {
+                x: player_image.width() as f64 * scale_factor as f64 * POINTS_TO_METERS,
+                y: player_image.height() as f64 * scale_factor as f64 * POINTS_TO_METERS,
+            },
+        );
+        body.material = LEMON_MATERIAL;
+        body.collider = Collider::Circle;
+        body.rolling_friction = LEMON_ROLLING_FRICTION;
+
         Self {
-            phys: PlayerPhys::new(
-                loc,
-                mass,
-                F64x2 {
-                    x: player_image.width() as f64 * scale_factor as f64 * POINTS_TO_METERS,
-                    y: player_image.height() as f64 * scale_factor as f64 * POINTS_TO_METERS,
-                },
-            ),
+            body: physics.add(body),
             sprites,
-            jump_force,
             move_force,
+            launch_impulse: 0.8,
+            launch_cooldown: 0.5,
+            grapple_range: 6.0,
             debug_phys: false,
+            launch_cooldown_left: 0.0,
+            statuses: StatusEffects::default(),
+            jump_state: JumpState::new(jump_force),
+            grapple: None,
         }
     }
 
@@ -70,9 +106,12 @@ impl Player {
         gl: &mut GlGraphics,
         win_height: f64,
         map: &crate::WorldMap,
+        physics: &PhysicsWorld,
+        alpha: f64,
     ) {
-        let map_px_to_meter = map.map_px_to_meter;
-        let meter_to_map_px = 1.0 / map_px_to_meter;
+        let map_px_to_meter = map.meta.map_px_to_meter;
+        let phys = &physics[self.body];
+        let loc = phys.interpolated_loc(alpha);
         let globalize_physics_cord = move |coord: F64x2| -> F64x2 {
             F64x2 {
                 x: coord.x,
@@ -82,41 +121,53 @@ impl Player {
 
         use graphics::*;
 
+        // turned around its center, as far as the body has rolled
+        let center = globalize_physics_cord((loc + phys.size / 2.0) * METERS_TO_POINTS);
+        let half_size = phys.size / 2.0 * METERS_TO_POINTS;
         Image::new()
-            .rect(rectangle_by_points(
-                globalize_physics_cord(self.phys.loc * METERS_TO_POINTS),
-                globalize_physics_cord((self.phys.loc + self.phys.size) * METERS_TO_POINTS),
-            ))
+            .rect(rectangle_by_points(-half_size, half_size))
             .draw(
-                match self.phys.last_direction {
+                match phys.last_direction {
                     HorizontalDirection::Left => &self.sprites.0,
                     HorizontalDirection::Right => &self.sprites.1,
                 },
                 &graphics::DrawState::default(),
+                c.transform
+                    .trans(
+                        -map.cam_loc.x * METERS_TO_POINTS,
+                        map.cam_loc.y * METERS_TO_POINTS,
+                    )
+                    .trans(center.x, center.y)
+                    // y points down on screen, so counterclockwise turns the other way
+                    .rot_rad(-phys.interpolated_angle(alpha)),
+                gl,
+            );
+
+        if let Some(anchor) = self
+            .grapple
+            .and_then(|grapple| physics.constraint(grapple))
+            .and_then(|rope| physics.anchor_loc(rope.anchor))
+        {
+            line_from_to(
+                LIGHT_GREY,
+                1.5,
+                globalize_physics_cord((loc + phys.size / 2.0) * METERS_TO_POINTS),
+                globalize_physics_cord(anchor * METERS_TO_POINTS),
                 c.transform.trans(
                     -map.cam_loc.x * METERS_TO_POINTS,
                     map.cam_loc.y * METERS_TO_POINTS,
                 ),
                 gl,
             );
+        }
 
-        if self.debug_phys {
-            Rectangle::new(rgba(0, 243, 223, 0.3)).draw(
+        if self.can_launch(physics) {
+            let center = loc + phys.size / 2.0;
+            let radius = phys.size.x.max(phys.size.y) * 0.6;
+            Ellipse::new_border(LIGHT_TRANSPARENT_BLUE, 2.0).draw(
                 rectangle_by_points(
-                    globalize_physics_cord(
-                        F64x2::new(
-                            (self.phys.loc.x * meter_to_map_px).floor(),
-                            (self.phys.loc.y * meter_to_map_px).floor(),
-                        ) * map_px_to_meter
-                            * METERS_TO_POINTS,
-                    ),
-                    globalize_physics_cord(
-                        F64x2::new(
-                            (self.phys.loc.x * meter_to_map_px).floor() + 3.0,
-                            (self.phys.loc.y * meter_to_map_px).floor() + 4.0,
-                        ) * map_px_to_meter
-                            * METERS_TO_POINTS,
-                    ),
+                    globalize_physics_cord((center - radius) * METERS_TO_POINTS),
+                    globalize_physics_cord((center + radius) * METERS_TO_POINTS),
                 ),
                 &DrawState::default(),
                 c.transform.trans(
@@ -125,89 +176,60 @@ impl Player {
                 ),
                 gl,
             );
+        }
 
-            //y min
-            let mut render_y_min = |value: f64, offset: f64| {
-                line_from_to(
-                    rgba(0, 255, 0, 0.2),
-                    5.0,
-                    globalize_physics_cord(
-                        (self.phys.loc + F64x2::new(offset, 0.0) * map_px_to_meter) * METERS_TO_POINTS,
-                    ),
-                    globalize_physics_cord(
-                        (F64x2::new(self.phys.loc.x, value)
-                            + F64x2::new(offset, 0.1 /* so it shows a small gap */) * map_px_to_meter)
-                            * METERS_TO_POINTS,
-                    ),
-                    c.transform.trans(
-                        -map.cam_loc.x * METERS_TO_POINTS,
-                        map.cam_loc.y * METERS_TO_POINTS,
-                    ),
-                    gl,
-                );
-            };
-            render_y_min(self.phys.y0_min, 0.0);
-            render_y_min(self.phys.y1_min, 1.0);
-            render_y_min(self.phys.y2_min, 2.0);
-            // y max
-            let mut render_y_max = |value: f64, offset: f64| {
-                line_from_to(
-                    rgba(0, 255, 0, 0.2),
-                    5.0,
-                    globalize_physics_cord(
-                        (self.phys.loc + F64x2::new(offset, 3.0) * map_px_to_meter) * METERS_TO_POINTS,
-                    ),
-                    globalize_physics_cord(
-                        (F64x2::new(self.phys.loc.x, value)
-                            + F64x2::new(offset, -1.1 /* so it shows a small gap */) * map_px_to_meter)
-                            * METERS_TO_POINTS,
-                    ),
-                    c.transform.trans(
-                        -map.cam_loc.x * METERS_TO_POINTS,
-                        map.cam_loc.y * METERS_TO_POINTS,
-                    ),
-                    gl,
-                );
-            };
-            render_y_max(self.phys.y0_max, 0.0);
-            render_y_max(self.phys.y1_max, 1.0);
-            render_y_max(self.phys.y2_max, 2.0);
-            // x min
-            let mut render_x_min = |value: f64, offset: f64| {
-                line_from_to(
-                    rgba(0, 255, 0, 0.2),
-                    5.0,
-                    globalize_physics_cord(
-                        (self.phys.loc + F64x2::new(0.0, offset) * map_px_to_meter) * METERS_TO_POINTS,
-                    ),
-                    globalize_physics_cord(
-                        (F64x2::new(value, self.phys.loc.y)
-                            + F64x2::new(0.1, offset) * map_px_to_meter)
-                            * METERS_TO_POINTS,
+        if self.debug_phys {
+            // one bar per status above the player, shrinking as it runs out
+            let bar_height = 0.04;
+            for (i, status) in self.statuses.active.iter().enumerate() {
+                let color = match status.kind {
+                    StatusKind::SpeedBoost(_) => rgba(230, 180, 50, 0.8),
+                    StatusKind::GravityScale(_) => rgba(155, 92, 255, 0.8),
+                };
+                let fraction = (status.remaining / status.duration).clamp(0.0, 1.0);
+                let bottom_left = loc
+                    + F64x2::new(0.0, phys.size.y + bar_height * (1.5 * i as f64 + 0.5));
+                Rectangle::new(color).draw(
+                    rectangle_by_points(
+                        globalize_physics_cord(bottom_left * METERS_TO_POINTS),
+                        globalize_physics_cord(
+                            (bottom_left + F64x2::new(phys.size.x * fraction, bar_height))
+                                * METERS_TO_POINTS,
+                        ),
                     ),
+                    &DrawState::default(),
                     c.transform.trans(
                         -map.cam_loc.x * METERS_TO_POINTS,
                         map.cam_loc.y * METERS_TO_POINTS,
                     ),
                     gl,
                 );
-            };
-            render_x_min(self.phys.x0_min, 0.0);
-            render_x_min(self.phys.x1_min, 1.0);
-            render_x_min(self.phys.x2_min, 2.0);
-            render_x_min(self.phys.x3_min, 3.0);
-            // x max
-            let mut render_x_max = |value: f64, offset: f64| {
+            }
+
+            // hitbox
+            Rectangle::new(rgba(0, 243, 223, 0.3)).draw(
+                rectangle_by_points(
+                    globalize_physics_cord(loc * METERS_TO_POINTS),
+                    globalize_physics_cord((loc + phys.size) * METERS_TO_POINTS),
+                ),
+                &DrawState::default(),
+                c.transform.trans(
+                    -map.cam_loc.x * METERS_TO_POINTS,
+                    map.cam_loc.y * METERS_TO_POINTS,
+                ),
+                gl,
+            );
+
+            // collision normals, from the middle of the side that hit something
+            for hit in &phys.hits {
+                let center = loc + phys.size / 2.0;
+                let side_center = center + hit.side.direction() * phys.size / 2.0;
                 line_from_to(
-                    rgba(0, 255, 0, 0.2),
-                    5.0,
-                    globalize_physics_cord(
-                        (self.phys.loc + F64x2::new(2.0, offset) * map_px_to_meter) * METERS_TO_POINTS,
-                    ),
+                    rgba(0, 255, 0, 0.6),
+                    2.0,
+                    globalize_physics_cord(side_center * METERS_TO_POINTS),
                     globalize_physics_cord(
-                        (F64x2::new(value, self.phys.loc.y)
-                            + F64x2::new(-0.1, offset) * map_px_to_meter)
-                            * METERS_TO_POINTS,
+                        (side_center + hit.normal * map_px_to_meter * 2.0) * METERS_TO_POINTS,
                     ),
                     c.transform.trans(
                         -map.cam_loc.x * METERS_TO_POINTS,
@@ -215,19 +237,97 @@ impl Player {
                     ),
                     gl,
                 );
-            };
-            render_x_max(self.phys.x0_max, 0.0);
-            render_x_max(self.phys.x1_max, 1.0);
-            render_x_max(self.phys.x2_max, 2.0);
-            render_x_max(self.phys.x3_max, 3.0);
+            }
         }
     }
 
-    pub fn update_phys(&mut self, dt: f64, map: &crate::WorldMap) {
-        self.phys.update(dt, map)
+    /// where to draw the player, `alpha` of the way from the previous physics step to the current one
+    pub fn interpolated_loc(&self, physics: &PhysicsWorld, alpha: f64) -> F64x2 {
+        physics[self.body].interpolated_loc(alpha)
     }
 
-    pub fn jump(&mut self) {
-        self.phys.vel.y += self.jump_force;
+    /// applies the player's statuses to their body, before [`PhysicsWorld::step`]
+    pub fn before_step(&mut self, dt: f64, physics: &mut PhysicsWorld) {
+        self.launch_cooldown_left = (self.launch_cooldown_left - dt).max(0.0);
+        self.statuses.tick(dt, &mut physics[self.body]);
+    }
+
+    /// takes granted statuses and runs buffered jumps, after [`PhysicsWorld::step`]
+    pub fn after_step(&mut self, dt: f64, physics: &mut PhysicsWorld) {
+        let phys = &mut physics[self.body];
+        for status in phys.granted_statuses.drain(..) {
+            debug!("granted status {:?}", status);
+            self.statuses.grant(status);
+        }
+        self.jump_state.after_step(dt, phys);
+    }
+
+    /// gives the player a timed status, from a pickup or the like
+    pub fn grant_status(&mut self, status: StatusEffect) {
+        self.statuses.grant(status);
+    }
+
+    /// jumps if the player can, otherwise remembers the press for [`JumpState::jump_buffer_time`]
+    pub fn jump(&mut self, physics: &mut PhysicsWorld) {
+        self.jump_state.press(&mut physics[self.body]);
+    }
+
+    /// makes the jump lower, if the player is still rising from it
+    pub fn release_jump(&mut self, physics: &mut PhysicsWorld) {
+        self.jump_state.release(&mut physics[self.body]);
+    }
+
+    /// shoots the grappling hook in the direction the player is moving in (or up and forward, if they are not).
+    /// if it catches on a collision tile within [`Player::grapple_range`], the player hangs from it on a rope
+    pub fn grapple(&mut self, physics: &mut PhysicsWorld, map: &crate::WorldMap) {
+        self.release_grapple(physics);
+        let phys = &physics[self.body];
+        let forward = match phys.last_direction {
+            HorizontalDirection::Left => -1.0,
+            HorizontalDirection::Right => 1.0,
+        };
+        let direction = phys
+            .movement_forces
+            .normalized()
+            .unwrap_or_else(|| F64x2::new(forward, 1.0).normalized().unwrap());
+        let hit = match map.raycast(phys.center(), direction, self.grapple_range) {
+            Some(hit) => hit,
+            None => return,
+        };
+        debug!("grappled at {:?}", hit.point);
+        self.grapple = Some(physics.add_constraint(Constraint::rope(
+            self.body,
+            Anchor::Point(hit.point),
+            hit.distance,
+        )));
+    }
+
+    /// lets go of the grappling hook, if it is caught on something
+    pub fn release_grapple(&mut self, physics: &mut PhysicsWorld) {
+        if let Some(grapple) = self.grapple.take() {
+            physics.remove_constraint(grapple);
+        }
+    }
+
+    /// if the player is in a launch tile, and the launch has cooled down
+    pub fn can_launch(&self, physics: &PhysicsWorld) -> bool {
+        physics[self.body].launch_strength.is_some() && self.launch_cooldown_left <= 0.0
+    }
+
+    /// launches the player in the direction they are moving in (or up, if they are not),
+    /// if they are in a launch tile
+    pub fn launch(&mut self, physics: &mut PhysicsWorld) {
+        let strength = match physics[self.body].launch_strength {
+            Some(strength) if self.can_launch(physics) => strength,
+            _ => return,
+        };
+        let phys = &mut physics[self.body];
+        let direction = phys
+            .movement_forces
+            .normalized()
+            .or_else(|| phys.vel.normalized())
+            .unwrap_or(F64x2::new(0.0, 1.0));
+        phys.apply_impulse(direction * self.launch_impulse * strength);
+        self.launch_cooldown_left = self.launch_cooldown;
     }
 }
@@ -0,0 +1,89 @@
+//! running the simulation in fixed size steps, independent of how often the game gets updated
+
+/// collects the time passed between updates, and hands it out in steps of exactly `step` seconds
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    /// seconds per step
+    pub step: f64,
+    /// most steps [`FixedTimestep::advance`] returns at once. time beyond that is dropped
+    pub max_steps: u32,
+    /// seconds passed that have not been simulated yet, always less than `step` after [`FixedTimestep::advance`]
+    accumulator: f64,
+}
+
+impl FixedTimestep {
+    pub fn new(step: f64, max_steps: u32) -> Self {
+        Self {
+            step,
+            max_steps,
+            accumulator: 0.0,
+        }
+    }
+
+    /// adds `dt` seconds of passed time, and returns how many steps to simulate now
+    pub fn advance(&mut self, dt: f64) -> u32 {
+        self.accumulator += dt;
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            if steps == self.max_steps {
+                warn!("physics is {:.3}s behind, skipping ahead", self.accumulator);
+                self.accumulator %= self.step;
+                break;
+            }
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+        steps
+    }
+
+    /// how far (0 to 1) the time passed is between the last step and the next one, for interpolating when drawing
+    pub fn alpha(&self) -> f64 {
+        self.alpha_after(0.0)
+    }
+
+    /// like [`FixedTimestep::alpha`], `dt` seconds after the last [`FixedTimestep::advance`]
+    pub fn alpha_after(&self, dt: f64) -> f64 {
+        ((self.accumulator + dt) / self.step).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uneven_updates_keep_the_remainder() {
+        let mut timestep = FixedTimestep::new(0.01, 25);
+        assert_eq!(timestep.advance(0.025), 2);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-9);
+        assert_eq!(timestep.advance(0.004), 0);
+        assert!((timestep.alpha() - 0.9).abs() < 1e-9);
+        assert!((timestep.alpha_after(0.0005) - 0.95).abs() < 1e-9);
+        assert_eq!(timestep.alpha_after(0.5), 1.0);
+        assert_eq!(timestep.advance(0.007), 1);
+        assert!((timestep.alpha() - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn same_time_gives_same_steps_however_it_is_split() {
+        let mut even = FixedTimestep::new(0.01, 25);
+        let mut uneven = FixedTimestep::new(0.01, 25);
+        let even_steps = (0..30).map(|_| even.advance(0.0165)).sum::<u32>();
+        let uneven_steps = [0.003, 0.021, 0.0, 0.1, 0.04, 0.016, 0.2, 0.115]
+            .iter()
+            .map(|dt| uneven.advance(*dt))
+            .sum::<u32>();
+        assert_eq!(even_steps, 49);
+        assert_eq!(uneven_steps, 49);
+        assert!((even.alpha() - uneven.alpha()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn long_hitches_are_capped() {
+        let mut timestep = FixedTimestep::new(0.01, 25);
+        assert_eq!(timestep.advance(1.003), 25);
+        // the rest of the hitch is dropped, only the part of a step is kept
+        assert!((timestep.alpha() - 0.3).abs() < 1e-6);
+        assert_eq!(timestep.advance(0.01), 1);
+    }
+}
@@ -2,7 +2,11 @@ use std::ops::{
     Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Sub, SubAssign,
 };
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+/// (de)serialized as a `(x, y)` tuple
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "[f64; 2]", into = "[f64; 2]")]
 pub struct F64x2 {
     pub x: f64,
     pub y: f64,
@@ -21,6 +25,11 @@ impl F64x2 {
         F64x2::new(0.0, 0.0)
     }
 
+    /// dot product of two vectors
+    pub fn dot(self, other: Self) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
     /// cross product of two vectors
     pub fn cross_2v(self, other: Self) -> f64 {
         self.x * other.y - self.y * other.x
@@ -43,6 +52,25 @@ impl F64x2 {
         ((other.x - self.x).powi(2) + (other.y - self.y).powi(2)).sqrt() - (r2 + r1)
     }
 
+    pub fn length(self) -> f64 {
+        (self.x * self.x + self.y * self.y).sqrt()
+    }
+
+    /// unit vector in the same direction, or `None` for the zero vector
+    pub fn normalized(self) -> Option<Self> {
+        let length = self.length();
+        if length > 0.0 {
+            Some(self / length)
+        } else {
+            None
+        }
+    }
+
+    /// linear interpolation, `self` at `t = 0` and `other` at `t = 1`
+    pub fn lerp(self, other: Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+
     pub fn floor(self) -> Self {
         Self {
             x: self.x.floor(),
@@ -251,8 +279,8 @@ impl From<[f64; 2]> for F64x2 {
     }
 }
 
-impl Into<[f64; 2]> for F64x2 {
-    fn into(self) -> [f64; 2] {
-        [self.x, self.y]
+impl From<F64x2> for [f64; 2] {
+    fn from(val: F64x2) -> Self {
+        [val.x, val.y]
     }
 }
@@ -0,0 +1,135 @@
+//! compact per tile collision data, built from the effect layer when the map is loaded
+//!
+//! looking a tile up in the grid is a bounds check and two array reads,
+//! instead of reading the pixel and hashing its color into the [`TileEffectMap`]
+
+use std::collections::HashMap;
+
+use image::Rgba;
+
+use super::{
+    material::{MaterialTable, PhysicsMaterial},
+    MapImage, TileEffect, TileEffectCondition, TileEffectMap,
+};
+
+/// everything physics needs to know about one color of the effect layer
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileMaterial {
+    pub color: Rgba<u8>,
+    /// the tile's physics material, if this is a collision tile
+    pub collision: Option<PhysicsMaterial>,
+    pub effects: Vec<TileEffect>,
+    pub conditions: Vec<TileEffectCondition>,
+}
+
+impl TileMaterial {
+    fn new(color: Rgba<u8>, effect_map: &TileEffectMap, materials: &MaterialTable) -> Self {
+        let (effects, conditions) = effect_map.get(&color).cloned().unwrap_or_default();
+        let collision = effects.iter().find_map(|effect| match effect {
+            TileEffect::Collision(name) => Some(materials.get(name).copied().unwrap_or_else(|| {
+                warn!("unknown physics material {:?}, using a frictionless one", name);
+                PhysicsMaterial::default()
+            })),
+            _ => None,
+        });
+        Self {
+            color,
+            collision,
+            effects,
+            conditions,
+        }
+    }
+
+    pub fn is_solid(&self) -> bool {
+        self.collision.is_some()
+    }
+}
+
+/// tile coordinates used by the grid are in map pixels from the bottom left of the map, like physics coordinates
+#[derive(Debug, Clone)]
+pub struct CollisionGrid {
+    width: u32,
+    height: u32,
+    /// index into `materials` for every tile, row major starting at the bottom row
+    tiles: Vec<u32>,
+    /// one entry for every color used on the effect layer
+    pub materials: Vec<TileMaterial>,
+}
+
+impl CollisionGrid {
+    pub fn new(effect: &MapImage, effect_map: &TileEffectMap, materials: &MaterialTable) -> Self {
+        let (width, height) = effect.dimensions();
+        let mut tile_materials: Vec<TileMaterial> = vec![];
+        let mut material_indices: HashMap<Rgba<u8>, u32> = HashMap::new();
+        let mut tiles = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let color = *effect.get_pixel(x, height - 1 - y);
+                let index = *material_indices.entry(color).or_insert_with(|| {
+                    tile_materials.push(TileMaterial::new(color, effect_map, materials));
+                    // an image can not have more colors than pixels, so this always fits
+                    (tile_materials.len() - 1) as u32
+                });
+                tiles.push(index);
+            }
+        }
+
+        Self {
+            width,
+            height,
+            tiles,
+            materials: tile_materials,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn index(&self, x: i64, y: i64) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            None
+        } else {
+            Some(y as usize * self.width as usize + x as usize)
+        }
+    }
+
+    /// the material of the tile at `(x, y)`, or `None` if it is outside the map
+    pub fn get(&self, x: i64, y: i64) -> Option<&TileMaterial> {
+        self.index(x, y)
+            .map(|i| &self.materials[self.tiles[i] as usize])
+    }
+
+    /// if the tile at `(x, y)` is a collision tile. tiles outside the map are not
+    pub fn is_solid(&self, x: i64, y: i64) -> bool {
+        self.get(x, y).is_some_and(TileMaterial::is_solid)
+    }
+
+    /// changes the tile at `(x, y)` to `color`. does nothing if it is outside the map
+    pub fn set_tile(
+        &mut self,
+        x: i64,
+        y: i64,
+        color: Rgba<u8>,
+        effect_map: &TileEffectMap,
+        materials: &MaterialTable,
+    ) {
+        let i = match self.index(x, y) {
+            Some(i) => i,
+            None => return,
+        };
+        let index = match self.materials.iter().position(|m| m.color == color) {
+            Some(index) => index,
+            None => {
+                self.materials
+                    .push(TileMaterial::new(color, effect_map, materials));
+                self.materials.len() - 1
+            }
+        };
+        self.tiles[i] = index as u32;
+    }
+}
@@ -0,0 +1,116 @@
+//! named physics materials, which collision tiles refer to with [`TileEffect::Collision`]
+//!
+//! materials are loaded from [`MATERIALS_FILE`] in the map's directory:
+//!
+//! ```ron
+//! {
+//!     "ice": (static_friction: 0.1, kinetic_friction: 0.03, restitution: 0.05),
+//!     "rubber": (static_friction: 0.9, kinetic_friction: 0.8, restitution: 0.9),
+//! }
+//! ```
+
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+
+use super::{meta::MapMeta, TileEffect, TileEffectMap};
+
+/// name of the material table shared by all maps in the same directory
+pub const MATERIALS_FILE: &str = "materials.ron";
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+pub struct PhysicsMaterial {
+    /// friction coeff while not sliding. a body at rest only starts to slide
+    /// when pushed along the surface harder than this times the normal force
+    pub static_friction: f64,
+    /// friction coeff while sliding
+    pub kinetic_friction: f64,
+    /// bounce factor, the fraction of the speed into the surface that is kept when bouncing off it
+    pub restitution: f64,
+}
+
+impl PhysicsMaterial {
+    /// leaves the other material as it is when combined with it
+    pub const NEUTRAL: Self = Self {
+        static_friction: 1.0,
+        kinetic_friction: 1.0,
+        restitution: 0.0,
+    };
+
+    /// the largest of each value of both materials
+    pub fn max_each(self, other: PhysicsMaterial) -> Self {
+        Self {
+            static_friction: self.static_friction.max(other.static_friction),
+            kinetic_friction: self.kinetic_friction.max(other.kinetic_friction),
+            restitution: self.restitution.max(other.restitution),
+        }
+    }
+
+    /// the material of a surface as felt by a body made of `body`.
+    /// friction coeffs multiply, and the bouncier restitution is used
+    pub fn combine(self, body: PhysicsMaterial) -> Self {
+        Self {
+            static_friction: self.static_friction * body.static_friction,
+            kinetic_friction: self.kinetic_friction * body.kinetic_friction,
+            restitution: self.restitution.max(body.restitution),
+        }
+    }
+}
+
+pub type MaterialTable = HashMap<String, PhysicsMaterial>;
+
+/// loads the material table of the map's directory, or an empty table if there is none
+pub fn load_for_map(map_path: &Path) -> Result<MaterialTable> {
+    let path = map_path
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(MATERIALS_FILE);
+    if !path.is_file() {
+        warn!("no material table found at {}", path.display());
+        return Ok(HashMap::new());
+    }
+    let src = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read materials {}", path.display()))?;
+    ron::from_str(&src).map_err(|e| {
+        anyhow!(
+            "{}:{}:{}: {}",
+            path.display(),
+            e.position.line,
+            e.position.col,
+            e.code
+        )
+    })
+}
+
+/// makes sure every material used by `effect_map` and the map's platforms is in `materials`
+pub fn check_map(
+    effect_map: &TileEffectMap,
+    meta: &MapMeta,
+    materials: &MaterialTable,
+) -> Result<()> {
+    let mut missing = effect_map
+        .values()
+        .flat_map(|(effects, _)| effects)
+        .filter_map(|effect| match effect {
+            TileEffect::Collision(name) => Some(name.as_str()),
+            _ => None,
+        })
+        .chain(
+            meta.platforms
+                .iter()
+                .map(|platform| platform.material.as_str()),
+        )
+        .filter(|name| !materials.contains_key(*name))
+        .collect::<Vec<&str>>();
+    if !missing.is_empty() {
+        missing.sort_unstable();
+        missing.dedup();
+        bail!(
+            "unknown physics materials {:?}, they have to be defined in {}",
+            missing,
+            MATERIALS_FILE
+        );
+    }
+    Ok(())
+}
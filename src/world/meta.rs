@@ -0,0 +1,199 @@
+//! per map metadata, loaded from `<map name>.map.ron`
+//!
+//! ```ron
+//! (
+//!     name: "test map 3",
+//!     spawn: Pixels((5.0, 94.0)),
+//!     map_px_to_meter: 0.2,
+//!     gravity: (0.0, -9.80665),
+//!     linear_drag: 0.0,
+//!     quadratic_drag: 0.01,
+//!     clear_color: "#80ccccff",
+//!     par_time: Some(30.0),
+//!     background: Some("test_map_3.background.png"),
+//!     foreground: Some("test_map_3.foreground.png"),
+//!     draw_effect_layer: false,
+//!     platforms: [
+//!         (
+//!             size: (2.0, 0.4),
+//!             material: "metal",
+//!             waypoints: [(4.0, 3.0), (10.0, 3.0)],
+//!             speed: 1.5,
+//!             mode: PingPong,
+//!         ),
+//!     ],
+//! )
+//! ```
+//!
+//! every field is optional, missing fields use the values from [`MapMeta::default`]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Deserializer};
+
+use super::palette::parse_color;
+use crate::{colors::*, constants::*, vec2::F64x2};
+
+/// extension (replacing `.png`) of the map metadata file
+pub const META_EXTENSION: &str = "map.ron";
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum SpawnPoint {
+    /// image coordinates of the pixel the player spawns in (from the top left of the map)
+    Pixels(F64x2),
+    /// world coordinates of the players bottom left corner
+    Meters(F64x2),
+}
+
+/// what a platform does after reaching its last waypoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum PathMode {
+    /// goes back the way it came
+    #[default]
+    PingPong,
+    /// goes straight to the first waypoint, and around again
+    Loop,
+}
+
+/// a moving platform, which follows its waypoints at a constant speed
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PlatformPath {
+    /// m, width and height
+    pub size: F64x2,
+    /// name of the platform's [`super::material::PhysicsMaterial`], like [`super::TileEffect::Collision`]
+    pub material: String,
+    /// m, where the platform's bottom left corner goes, in order. it starts at the first one
+    pub waypoints: Vec<F64x2>,
+    /// m/s
+    pub speed: f64,
+    #[serde(default)]
+    pub mode: PathMode,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct MapMeta {
+    /// display name. defaults to the file name of the map
+    pub name: String,
+    pub spawn: SpawnPoint,
+    /// size of a single map pixel
+    pub map_px_to_meter: f64,
+    /// m/s^2
+    pub gravity: F64x2,
+    /// N*s/m, drag of the air everywhere on the map is `-linear_drag * vel`
+    pub linear_drag: f64,
+    /// N*s^2/m^2, drag of the air everywhere on the map is `-quadratic_drag * |vel| * vel`
+    pub quadratic_drag: f64,
+    /// background color, written as `#rrggbb` or `#rrggbbaa`
+    #[serde(deserialize_with = "deserialize_color")]
+    pub clear_color: Color,
+    /// target time to complete the map in, in seconds
+    pub par_time: Option<f64>,
+    /// image drawn behind the player, relative to the map's directory
+    pub background: Option<PathBuf>,
+    /// image drawn in front of the player, relative to the map's directory
+    pub foreground: Option<PathBuf>,
+    /// if the effect layer (the map image itself) is drawn between the background and the player.
+    /// turn this off when the background already contains the map's art
+    pub draw_effect_layer: bool,
+    pub platforms: Vec<PlatformPath>,
+}
+
+impl Default for MapMeta {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            spawn: SpawnPoint::Meters(F64x2::splat(1.0)),
+            map_px_to_meter: 1.0 / 5.0,
+            gravity: GRAVITY,
+            linear_drag: 0.0,
+            quadratic_drag: DEFAULT_QUADRATIC_DRAG,
+            clear_color: rgba(128, 204, 204, 1.0),
+            par_time: None,
+            background: None,
+            foreground: None,
+            draw_effect_layer: true,
+            platforms: vec![],
+        }
+    }
+}
+
+impl MapMeta {
+    /// loads the metadata file for the map at `map_path`, or uses the defaults if it does not have one
+    pub fn load_for_map(map_path: &Path) -> Result<Self> {
+        let meta_path = sidecar_path(map_path);
+        let mut meta = if meta_path.is_file() {
+            let src = fs::read_to_string(&meta_path)
+                .with_context(|| format!("failed to read map metadata {}", meta_path.display()))?;
+            ron::from_str::<Self>(&src).map_err(|e| {
+                anyhow!(
+                    "{}:{}:{}: {}",
+                    meta_path.display(),
+                    e.position.line,
+                    e.position.col,
+                    e.code
+                )
+            })?
+        } else {
+            debug!(
+                "no map metadata found at {}, using defaults",
+                meta_path.display()
+            );
+            Self::default()
+        };
+        if meta.name.is_empty() {
+            meta.name = map_path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+        }
+        if meta.map_px_to_meter <= 0.0 {
+            bail!(
+                "{}: map_px_to_meter must be positive, got {}",
+                meta_path.display(),
+                meta.map_px_to_meter
+            );
+        }
+        for (i, platform) in meta.platforms.iter().enumerate() {
+            if platform.waypoints.is_empty() || platform.speed <= 0.0 {
+                bail!(
+                    "{}: platform {} needs at least one waypoint and a positive speed",
+                    meta_path.display(),
+                    i
+                );
+            }
+        }
+        Ok(meta)
+    }
+
+    /// spawn location in meters, for a map `map_height` pixels tall
+    pub fn spawn_loc(&self, map_height: u32) -> F64x2 {
+        match self.spawn {
+            SpawnPoint::Pixels(px) => {
+                F64x2::new(px.x, map_height as f64 - px.y - 1.0) * self.map_px_to_meter
+            }
+            SpawnPoint::Meters(loc) => loc,
+        }
+    }
+}
+
+/// path of the metadata file for the map image at `map_path`
+pub fn sidecar_path(map_path: &Path) -> PathBuf {
+    map_path.with_extension(META_EXTENSION)
+}
+
+fn deserialize_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    let color = parse_color(&s).ok_or_else(|| {
+        serde::de::Error::custom(format!(
+            "invalid color {:?}, expected `#rrggbb` or `#rrggbbaa`",
+            s
+        ))
+    })?;
+    let [r, g, b, a] = color.0;
+    Ok(rgba(r, g, b, a as f32 / 255.0))
+}
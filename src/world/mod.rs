@@ -0,0 +1,311 @@
+pub mod grid;
+pub mod material;
+pub mod meta;
+pub mod palette;
+pub mod query;
+pub mod render;
+pub mod tiled;
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use image::{io::Reader as ImageReader, ImageBuffer, Rgba};
+use opengl_graphics::GlGraphics;
+use serde::Deserialize;
+use crate::{physics::status::StatusEffect, vec2::F64x2};
+
+use grid::CollisionGrid;
+use material::MaterialTable;
+use meta::MapMeta;
+use render::LayerTexture;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum TileEffectCondition {
+    /// the tile is directly below the player, and the player is resting on it
+    StandingOn,
+    /// the tile overlaps the player
+    InsideOf,
+    /// the tile is directly to the left of the player, and the player is against it
+    TouchingLeft,
+    /// the tile is directly to the right of the player, and the player is against it
+    TouchingRight,
+    /// the tile is directly above the player, and the player's head is against it
+    TouchingHead,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum TileEffect {
+    /// base collision type. this does not require any [`TileEffectCondition`] to take effect,
+    /// it just signals that this is a collision block
+    ///
+    /// ( name of the [`material::PhysicsMaterial`] )
+    Collision(String),
+    /// faster horizontal speed ( multiplier )
+    HorizontalSpeedBoost(f64),
+    /// enables the launch action ( launch strength )
+    LaunchEnable(f64),
+    /// constant force ( force )
+    Wind(F64x2),
+    /// extra drag on top of the map's, like [`meta::MapMeta::linear_drag`] and [`meta::MapMeta::quadratic_drag`]
+    /// ( linear drag, quadratic drag )
+    Drag(f64, f64),
+    /// gives the player a timed status, once each time the conditions start holding ( status )
+    GrantStatus(StatusEffect),
+}
+
+/// relationship between effects and conditions is as folows:
+///
+/// **any** condition being true means **all** effects apply
+pub type TileEffectMap = HashMap<Rgba<u8>, (Vec<TileEffect>, Vec<TileEffectCondition>)>;
+
+pub type MapImage = ImageBuffer<Rgba<u8>, Vec<u8>>;
+
+/// layers of a map, from back to front
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MapLayer {
+    Background,
+    Effect,
+    Foreground,
+}
+
+/// the images making up a map, all of the same size
+#[derive(Debug)]
+pub struct MapLayers {
+    /// drawn behind everything else, does not affect physics
+    pub background: Option<MapImage>,
+    /// collision and effect source, this is the only layer physics looks at
+    pub effect: MapImage,
+    /// drawn over the player, does not affect physics
+    pub foreground: Option<MapImage>,
+}
+
+impl MapLayers {
+    pub fn get(&self, layer: MapLayer) -> Option<&MapImage> {
+        match layer {
+            MapLayer::Background => self.background.as_ref(),
+            MapLayer::Effect => Some(&self.effect),
+            MapLayer::Foreground => self.foreground.as_ref(),
+        }
+    }
+
+    pub fn get_mut(&mut self, layer: MapLayer) -> Option<&mut MapImage> {
+        match layer {
+            MapLayer::Background => self.background.as_mut(),
+            MapLayer::Effect => Some(&mut self.effect),
+            MapLayer::Foreground => self.foreground.as_mut(),
+        }
+    }
+}
+
+/// a named point placed in the map editor, for spawning entities and the like
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapMarker {
+    pub name: String,
+    /// the object's type (or class) in the editor
+    pub kind: String,
+    /// m
+    pub loc: F64x2,
+}
+
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct WorldMap {
+    pub layers: MapLayers,
+    pub effect_map: TileEffectMap,
+    /// physics materials by name, for [`TileEffect::Collision`]
+    pub materials: MaterialTable,
+    /// built from `layers.effect`, `effect_map` and `materials`, call [`WorldMap::rebuild_grid`] after changing any
+    pub grid: CollisionGrid,
+    pub meta: MapMeta,
+    pub markers: Vec<MapMarker>,
+    pub cam_loc: F64x2,
+    /// one per [`MapLayer`], created on the first render
+    #[derivative(Debug = "ignore")]
+    textures: [Option<LayerTexture>; 3],
+}
+
+impl WorldMap {
+    pub fn new(
+        layers: MapLayers,
+        effect_map: TileEffectMap,
+        materials: MaterialTable,
+        meta: MapMeta,
+    ) -> Self {
+        let cam_loc = meta.spawn_loc(layers.effect.height());
+        let grid = CollisionGrid::new(&layers.effect, &effect_map, &materials);
+        Self {
+            layers,
+            effect_map,
+            materials,
+            grid,
+            meta,
+            markers: vec![],
+            cam_loc,
+            textures: [None, None, None],
+        }
+    }
+
+    /// a map with nothing but an effect layer, for tests
+    #[cfg(test)]
+    pub fn from_effect_image(
+        effect: MapImage,
+        effect_map: TileEffectMap,
+        materials: MaterialTable,
+        meta: MapMeta,
+    ) -> Self {
+        Self::new(
+            MapLayers {
+                background: None,
+                effect,
+                foreground: None,
+            },
+            effect_map,
+            materials,
+            meta,
+        )
+    }
+
+    /// loads a map from either a PNG image or a Tiled map (`.tmx` or `.tmj`), depending on the file extension
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("tmx" | "tmj" | "json") => Self::from_tiled(path),
+            _ => Self::from_path(path),
+        }
+    }
+
+    /// loads a map made in the [Tiled](https://www.mapeditor.org/) editor, see [`tiled`]
+    pub fn from_tiled<P: AsRef<Path>>(path: P) -> Result<Self> {
+        tiled::load(path.as_ref())
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+        let effect = ImageReader::open(path)?.decode()?.to_rgba8();
+
+        let effect_map = palette::load_for_map(path)?;
+        let materials = material::load_for_map(path)?;
+        let meta = MapMeta::load_for_map(path)?;
+        material::check_map(&effect_map, &meta, &materials)
+            .with_context(|| format!("in map {}", path.display()))?;
+
+        let load_layer = |layer: &Option<PathBuf>| -> Result<Option<MapImage>> {
+            let layer_path = match layer {
+                Some(layer_path) => dir.join(layer_path),
+                None => return Ok(None),
+            };
+            let image = ImageReader::open(&layer_path)
+                .with_context(|| format!("failed to open map layer {}", layer_path.display()))?
+                .decode()?
+                .to_rgba8();
+            if image.dimensions() != effect.dimensions() {
+                bail!(
+                    "map layer {} is {:?}, but the effect layer {} is {:?}",
+                    layer_path.display(),
+                    image.dimensions(),
+                    path.display(),
+                    effect.dimensions()
+                );
+            }
+            Ok(Some(image))
+        };
+        let background = load_layer(&meta.background)?;
+        let foreground = load_layer(&meta.foreground)?;
+
+        Ok(Self::new(
+            MapLayers {
+                background,
+                effect,
+                foreground,
+            },
+            effect_map,
+            materials,
+            meta,
+        ))
+    }
+
+    pub fn rebuild_grid(&mut self) {
+        self.grid = CollisionGrid::new(&self.layers.effect, &self.effect_map, &self.materials);
+    }
+
+    /// changes a single pixel of a layer (in image coordinates), keeping the collision grid and textures up to date
+    pub fn set_pixel(&mut self, layer: MapLayer, x: u32, y: u32, color: Rgba<u8>) {
+        let image = match self.layers.get_mut(layer) {
+            Some(image) => image,
+            None => {
+                warn!("tried to draw on the {:?} layer, but the map does not have one", layer);
+                return;
+            }
+        };
+        if x >= image.width() || y >= image.height() {
+            warn!(
+                "tried to draw at ({}, {}), outside of the {}x{} {:?} layer",
+                x,
+                y,
+                image.width(),
+                image.height(),
+                layer
+            );
+            return;
+        }
+        image.put_pixel(x, y, color);
+        if layer == MapLayer::Effect {
+            let height = self.layers.effect.height();
+            self.grid.set_tile(
+                x as i64,
+                (height - 1 - y) as i64,
+                color,
+                &self.effect_map,
+                &self.materials,
+            );
+        }
+        if let Some(texture) = &mut self.textures[layer as usize] {
+            texture.mark_dirty(x, y);
+        }
+    }
+
+    /// where the player should spawn, in meters
+    pub fn spawn_loc(&self) -> F64x2 {
+        self.meta.spawn_loc(self.layers.effect.height())
+    }
+
+    /// draws the layers that go behind the player, from back to front
+    pub fn render(&mut self, c: &graphics::Context, gl: &mut GlGraphics, win_size: [f64; 2]) {
+        self.render_layer(MapLayer::Background, c, gl, win_size);
+        if self.meta.draw_effect_layer {
+            self.render_layer(MapLayer::Effect, c, gl, win_size);
+        }
+    }
+
+    /// draws the layers that go in front of the player
+    pub fn render_foreground(
+        &mut self,
+        c: &graphics::Context,
+        gl: &mut GlGraphics,
+        win_size: [f64; 2],
+    ) {
+        self.render_layer(MapLayer::Foreground, c, gl, win_size);
+    }
+
+    fn render_layer(
+        &mut self,
+        layer: MapLayer,
+        c: &graphics::Context,
+        gl: &mut GlGraphics,
+        win_size: [f64; 2],
+    ) {
+        let image = match self.layers.get(layer) {
+            Some(image) => image,
+            None => return,
+        };
+        let texture = self.textures[layer as usize].get_or_insert_with(|| LayerTexture::new(image));
+        texture.sync(image);
+        texture.draw(self.meta.map_px_to_meter, self.cam_loc, c, gl, win_size);
+    }
+}
@@ -0,0 +1,160 @@
+//! loading of tile effect palettes
+//!
+//! a palette is a RON file containing a list of entries, each mapping a map color to its effects and conditions:
+//!
+//! ```ron
+//! [
+//!     (
+//!         color: "#e6b432ff",
+//!         effects: [Collision("metal"), HorizontalSpeedBoost(2.0)],
+//!         conditions: [StandingOn],
+//!     ),
+//! ]
+//! ```
+//!
+//! every map first loads the shared [`DEFAULT_PALETTE_FILE`] from its directory, and then the entries from its own
+//! sidecar palette (`<map name>.palette.ron`), which replace default entries of the same color.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use image::Rgba;
+use serde::Deserialize;
+
+use super::{TileEffect, TileEffectCondition, TileEffectMap};
+
+/// name of the palette shared by all maps in the same directory
+pub const DEFAULT_PALETTE_FILE: &str = "default.palette.ron";
+/// extension (replacing `.png`) of the per map palette file
+pub const PALETTE_EXTENSION: &str = "palette.ron";
+
+#[derive(Debug, Deserialize)]
+struct PaletteEntry {
+    /// `#rrggbb` or `#rrggbbaa`
+    color: String,
+    #[serde(default)]
+    effects: Vec<TileEffect>,
+    #[serde(default)]
+    conditions: Vec<TileEffectCondition>,
+}
+
+/// parses a color in the form `#rrggbb` or `#rrggbbaa` (the `#` is optional)
+pub fn parse_color(s: &str) -> Option<Rgba<u8>> {
+    let hex = s.trim().trim_start_matches('#');
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+        return None;
+    }
+    let mut channels = [255u8; 4];
+    for (i, channel) in channels.iter_mut().enumerate().take(hex.len() / 2) {
+        *channel = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(Rgba(channels))
+}
+
+/// loads a single palette file
+pub fn load_palette<P: AsRef<Path>>(path: P) -> Result<TileEffectMap> {
+    let path = path.as_ref();
+    let src = fs::read_to_string(path)
+        .with_context(|| format!("failed to read palette {}", path.display()))?;
+    parse_palette(&src, path)
+}
+
+/// path of the sidecar palette for the map image at `map_path`
+pub fn sidecar_path(map_path: &Path) -> PathBuf {
+    map_path.with_extension(PALETTE_EXTENSION)
+}
+
+/// loads the default palette of the map's directory (if there is one),
+/// and applies the overrides from the map's sidecar palette (if there is one)
+pub fn load_for_map(map_path: &Path) -> Result<TileEffectMap> {
+    let default_path = map_path
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(DEFAULT_PALETTE_FILE);
+    let sidecar_path = sidecar_path(map_path);
+
+    let mut effect_map = HashMap::new();
+    if default_path.is_file() {
+        effect_map = load_palette(&default_path)?;
+    } else {
+        warn!("no default palette found at {}", default_path.display());
+    }
+    if sidecar_path.is_file() {
+        let overrides = load_palette(&sidecar_path)?;
+        debug!(
+            "applying {} palette overrides from {}",
+            overrides.len(),
+            sidecar_path.display()
+        );
+        effect_map.extend(overrides);
+    }
+    if effect_map.is_empty() {
+        warn!("map {} has an empty palette", map_path.display());
+    }
+    Ok(effect_map)
+}
+
+fn parse_palette(src: &str, path: &Path) -> Result<TileEffectMap> {
+    let entries: Vec<PaletteEntry> = ron::from_str(src).map_err(|e| {
+        let color = color_before_line(src, e.position.line)
+            .map(|color| format!(" (in entry for color {})", color))
+            .unwrap_or_default();
+        anyhow!(
+            "{}:{}:{}: {}{}",
+            path.display(),
+            e.position.line,
+            e.position.col,
+            e.code,
+            color
+        )
+    })?;
+
+    let mut effect_map = HashMap::new();
+    // entries are in file order, so each color is searched for after the previous one
+    let mut search_from = 0;
+    for entry in entries {
+        if let Some(i) = src[search_from..].find(&entry.color) {
+            search_from += i + entry.color.len();
+        }
+        let line = src[..search_from].matches('\n').count() + 1;
+
+        let color = match parse_color(&entry.color) {
+            Some(color) => color,
+            None => bail!(
+                "{}:{}: invalid color {:?}, expected `#rrggbb` or `#rrggbbaa`",
+                path.display(),
+                line,
+                entry.color
+            ),
+        };
+        if effect_map
+            .insert(color, (entry.effects, entry.conditions))
+            .is_some()
+        {
+            bail!(
+                "{}:{}: duplicate entry for color {}",
+                path.display(),
+                line,
+                entry.color
+            );
+        }
+    }
+    Ok(effect_map)
+}
+
+/// finds the color of the last entry starting at or before `line` (1 indexed), for error messages
+fn color_before_line(src: &str, line: usize) -> Option<&str> {
+    src.lines()
+        .take(line)
+        .filter_map(|l| {
+            let rest = &l[l.find("color")? + "color".len()..];
+            let rest = rest.trim_start().strip_prefix(':')?.trim_start();
+            let rest = rest.strip_prefix('"')?;
+            Some(&rest[..rest.find('"')?])
+        })
+        .last()
+}
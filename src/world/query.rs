@@ -0,0 +1,278 @@
+//! asking the map what is where, in meters, for physics, AI, the camera and debug drawing
+
+use image::Rgba;
+
+use super::{grid::TileMaterial, TileEffect, WorldMap};
+use crate::vec2::F64x2;
+
+/// where a ray first hit a solid tile
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    /// m
+    pub point: F64x2,
+    /// unit vector pointing away from the tile, out of the side the ray went in through
+    pub normal: F64x2,
+    /// m, from the start of the ray
+    pub distance: f64,
+    /// color of the tile hit
+    pub color: Rgba<u8>,
+}
+
+/// a tile overlapping a box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileOverlap<'a> {
+    /// tile coordinates, in map pixels from the bottom left of the map
+    pub x: i64,
+    pub y: i64,
+    pub tile: &'a TileMaterial,
+    /// m, width and height of the part of the tile inside the box
+    pub overlap: F64x2,
+}
+
+impl WorldMap {
+    /// the tile at `loc`, or `None` if it is outside the map
+    pub fn tile_at(&self, loc: F64x2) -> Option<&TileMaterial> {
+        let tile = loc / self.meta.map_px_to_meter;
+        self.grid.get(tile.x.floor() as i64, tile.y.floor() as i64)
+    }
+
+    /// the effects of the tile at `loc`, none outside the map
+    pub fn effects_at(&self, loc: F64x2) -> &[TileEffect] {
+        self.tile_at(loc).map_or(&[], |tile| &tile.effects)
+    }
+
+    /// if the tile at `loc` is a collision tile. the outside of the map is not
+    pub fn is_solid_at(&self, loc: F64x2) -> bool {
+        self.tile_at(loc).is_some_and(TileMaterial::is_solid)
+    }
+
+    /// every tile inside the map that overlaps the box at `loc` (bottom left corner) of `size`.
+    /// tiles only touching the edge of the box are left out
+    pub fn overlapping_tiles(&self, loc: F64x2, size: F64x2) -> Vec<TileOverlap<'_>> {
+        let map_px_to_meter = self.meta.map_px_to_meter;
+        let min = loc / map_px_to_meter;
+        let max = (loc + size) / map_px_to_meter;
+        let mut tiles = vec![];
+        for y in min.y.floor() as i64..max.y.ceil() as i64 {
+            for x in min.x.floor() as i64..max.x.ceil() as i64 {
+                let overlap = F64x2::new(
+                    max.x.min(x as f64 + 1.0) - min.x.max(x as f64),
+                    max.y.min(y as f64 + 1.0) - min.y.max(y as f64),
+                );
+                if overlap.x <= 0.0 || overlap.y <= 0.0 {
+                    continue;
+                }
+                if let Some(tile) = self.grid.get(x, y) {
+                    tiles.push(TileOverlap {
+                        x,
+                        y,
+                        tile,
+                        overlap: overlap * map_px_to_meter,
+                    });
+                }
+            }
+        }
+        tiles
+    }
+
+    /// if any collision tile overlaps the box at `loc` of `size`
+    pub fn overlaps_solid(&self, loc: F64x2, size: F64x2) -> bool {
+        self.overlapping_tiles(loc, size)
+            .iter()
+            .any(|overlap| overlap.tile.is_solid())
+    }
+
+    /// the first solid tile along the ray from `origin` in `direction` (a unit vector), at most `max_distance` away.
+    /// walks the tiles the ray passes through one by one, so it never skips any.
+    /// a ray starting inside a solid tile hits it right away, with the normal pointing back along the ray
+    pub fn raycast(&self, origin: F64x2, direction: F64x2, max_distance: f64) -> Option<RayHit> {
+        let map_px_to_meter = self.meta.map_px_to_meter;
+        let start = origin / map_px_to_meter;
+        let (mut x, mut y) = (start.x.floor() as i64, start.y.floor() as i64);
+        if let Some(tile) = self.grid.get(x, y).filter(|tile| tile.is_solid()) {
+            return Some(RayHit {
+                point: origin,
+                normal: -direction,
+                distance: 0.0,
+                color: tile.color,
+            });
+        }
+
+        // distance (in map pixels) along the ray to the next column and row of tiles, and between columns and rows
+        let first_crossing = |start: f64, tile: i64, direction: f64| {
+            if direction > 0.0 {
+                (tile as f64 + 1.0 - start) / direction
+            } else if direction < 0.0 {
+                (start - tile as f64) / -direction
+            } else {
+                f64::INFINITY
+            }
+        };
+        let mut next_x = first_crossing(start.x, x, direction.x);
+        let mut next_y = first_crossing(start.y, y, direction.y);
+        let (between_x, between_y) = (1.0 / direction.x.abs(), 1.0 / direction.y.abs());
+        let (step_x, step_y) = (direction.x.signum() as i64, direction.y.signum() as i64);
+        let max_distance = max_distance / map_px_to_meter;
+        let (width, height) = (self.grid.width() as i64, self.grid.height() as i64);
+        loop {
+            // outside the map and heading further out, so there is nothing left to hit
+            if x < 0 && direction.x <= 0.0
+                || x >= width && direction.x >= 0.0
+                || y < 0 && direction.y <= 0.0
+                || y >= height && direction.y >= 0.0
+            {
+                return None;
+            }
+            let (distance, normal) = if next_x < next_y {
+                x += step_x;
+                next_x += between_x;
+                (next_x - between_x, F64x2::new(-step_x as f64, 0.0))
+            } else {
+                y += step_y;
+                next_y += between_y;
+                (next_y - between_y, F64x2::new(0.0, -step_y as f64))
+            };
+            if distance > max_distance || !distance.is_finite() {
+                return None;
+            }
+            if let Some(tile) = self.grid.get(x, y).filter(|tile| tile.is_solid()) {
+                return Some(RayHit {
+                    point: origin + direction * distance * map_px_to_meter,
+                    normal,
+                    distance: distance * map_px_to_meter,
+                    color: tile.color,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::world::{meta::MapMeta, MapImage, TileEffectCondition};
+
+    const AIR: Rgba<u8> = Rgba([0, 0, 0, 0]);
+    const STONE: Rgba<u8> = Rgba([128, 128, 128, 255]);
+    const WATER: Rgba<u8> = Rgba([0, 0, 255, 255]);
+
+    /// a map of 1m tiles from `rows`, top row first: '#' is stone, '~' is water, anything else is air
+    fn map(rows: &[&str]) -> WorldMap {
+        let mut effect = MapImage::new(rows[0].len() as u32, rows.len() as u32);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                let color = match c {
+                    '#' => STONE,
+                    '~' => WATER,
+                    _ => AIR,
+                };
+                effect.put_pixel(x as u32, y as u32, color);
+            }
+        }
+        let mut effect_map = HashMap::new();
+        effect_map.insert(
+            STONE,
+            (
+                vec![TileEffect::Collision("stone".to_string())],
+                vec![TileEffectCondition::StandingOn],
+            ),
+        );
+        effect_map.insert(
+            WATER,
+            (
+                vec![TileEffect::Wind(F64x2::new(0.0, 1.0))],
+                vec![TileEffectCondition::InsideOf],
+            ),
+        );
+        WorldMap::from_effect_image(
+            effect,
+            effect_map,
+            HashMap::new(),
+            MapMeta {
+                map_px_to_meter: 1.0,
+                ..MapMeta::default()
+            },
+        )
+    }
+
+    #[test]
+    fn raycast_hits_the_side_it_went_in_through() {
+        let map = map(&["....", "...#", "....", "####"]);
+        let hit = map
+            .raycast(F64x2::new(0.5, 2.5), F64x2::new(1.0, 0.0), 10.0)
+            .unwrap();
+        assert_eq!(hit.point, F64x2::new(3.0, 2.5));
+        assert_eq!(hit.normal, F64x2::new(-1.0, 0.0));
+        assert_eq!(hit.distance, 2.5);
+        assert_eq!(hit.color, STONE);
+
+        let down = F64x2::new(1.0, -1.0).normalized().unwrap();
+        let hit = map.raycast(F64x2::new(0.5, 3.3), down, 10.0).unwrap();
+        assert!((hit.point - F64x2::new(2.8, 1.0)).length() < 1e-9);
+        assert_eq!(hit.normal, F64x2::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn raycast_stops_at_max_distance() {
+        let map = map(&["....", "....", "....", "####"]);
+        let down = F64x2::new(0.0, -1.0);
+        assert!(map.raycast(F64x2::new(1.5, 3.5), down, 2.0).is_none());
+        assert!(map.raycast(F64x2::new(1.5, 3.5), down, 3.0).is_some());
+        // nothing but the edge of the map that way, even without a limit
+        assert!(map
+            .raycast(F64x2::new(1.5, 3.5), -down, f64::INFINITY)
+            .is_none());
+        let left = F64x2::new(-1.0, 0.0);
+        assert!(map
+            .raycast(F64x2::new(-1.0, 1.0), left, f64::INFINITY)
+            .is_none());
+        assert!(map
+            .raycast(F64x2::new(-1.0, 5.0), -left, f64::INFINITY)
+            .is_none());
+        // coming in from outside
+        let hit = map
+            .raycast(F64x2::new(-1.0, 0.5), -left, f64::INFINITY)
+            .unwrap();
+        assert_eq!((hit.distance, hit.normal), (1.0, left));
+        // already inside a tile
+        let hit = map.raycast(F64x2::new(1.5, 0.5), down, 1.0).unwrap();
+        assert_eq!(hit.distance, 0.0);
+    }
+
+    #[test]
+    fn point_queries_find_the_tile_and_its_effects() {
+        let map = map(&["....", ".~~.", "....", "####"]);
+        assert_eq!(
+            map.effects_at(F64x2::new(1.5, 2.2)),
+            &[TileEffect::Wind(F64x2::new(0.0, 1.0))]
+        );
+        assert!(map.effects_at(F64x2::new(0.5, 2.2)).is_empty());
+        assert!(map.effects_at(F64x2::new(-0.5, 2.2)).is_empty());
+        assert!(map.is_solid_at(F64x2::new(3.9, 0.1)));
+        assert!(!map.is_solid_at(F64x2::new(3.9, 1.1)));
+        assert_eq!(map.tile_at(F64x2::new(3.0, 5.0)), None);
+    }
+
+    #[test]
+    fn box_overlaps_tiles_it_reaches_into() {
+        let map = map(&["....", ".~~.", "....", "####"]);
+        // standing on the floor touches it, but does not overlap it
+        assert!(!map.overlaps_solid(F64x2::new(0.5, 1.0), F64x2::new(1.0, 1.0)));
+        assert!(map.overlaps_solid(F64x2::new(0.5, 0.9), F64x2::new(1.0, 1.0)));
+
+        let overlaps = map.overlapping_tiles(F64x2::new(0.5, 2.5), F64x2::new(1.0, 1.0));
+        let tiles = overlaps
+            .iter()
+            .map(|overlap| (overlap.x, overlap.y, overlap.tile.color))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tiles,
+            vec![(0, 2, AIR), (1, 2, WATER), (0, 3, AIR), (1, 3, AIR)]
+        );
+        assert!(overlaps
+            .iter()
+            .all(|overlap| overlap.overlap == F64x2::splat(0.5)));
+    }
+}
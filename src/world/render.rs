@@ -0,0 +1,95 @@
+//! drawing map layers as textures, uploaded to the gpu once and then updated only where the map changes
+
+use graphics::{Context, DrawState, Image, ImageSize, Transformed};
+use image::imageops;
+use opengl_graphics::{Filter, Format, GlGraphics, Texture, TextureSettings, UpdateTexture};
+
+use super::MapImage;
+use crate::{constants::*, utils::*, vec2::F64x2};
+
+/// a map layer uploaded to the gpu
+pub struct LayerTexture {
+    texture: Texture,
+    /// region changed since the last upload, in image coordinates.
+    /// ( min x, min y, max x, max y ), where the max is exclusive
+    dirty: Option<[u32; 4]>,
+}
+
+impl LayerTexture {
+    pub fn new(image: &MapImage) -> Self {
+        Self {
+            texture: Texture::from_image(image, &TextureSettings::new().filter(Filter::Nearest)),
+            dirty: None,
+        }
+    }
+
+    /// marks the pixel at `(x, y)` (image coordinates) to be uploaded again on the next [`LayerTexture::sync`]
+    pub fn mark_dirty(&mut self, x: u32, y: u32) {
+        self.dirty = Some(match self.dirty {
+            Some([x0, y0, x1, y1]) => [x0.min(x), y0.min(y), x1.max(x + 1), y1.max(y + 1)],
+            None => [x, y, x + 1, y + 1],
+        });
+    }
+
+    /// uploads the changed region of `image`, if anything changed
+    pub fn sync(&mut self, image: &MapImage) {
+        if let Some([x0, y0, x1, y1]) = self.dirty.take() {
+            let region = imageops::crop_imm(image, x0, y0, x1 - x0, y1 - y0).to_image();
+            // the inherent `Texture::update` re-uploads the whole image, so use the trait one
+            if let Err(e) = UpdateTexture::update(
+                &mut self.texture,
+                &mut (),
+                Format::Rgba8,
+                &region,
+                [x0, y0],
+                [x1 - x0, y1 - y0],
+            ) {
+                error!("failed to update map texture: {}", e);
+            }
+        }
+    }
+
+    /// draws the part of the layer that is visible with the camera at `cam_loc`
+    pub fn draw(
+        &self,
+        map_px_to_meter: f64,
+        cam_loc: F64x2,
+        c: &Context,
+        gl: &mut GlGraphics,
+        win_size: [f64; 2],
+    ) {
+        let (width, height) = (
+            self.texture.get_width() as f64,
+            self.texture.get_height() as f64,
+        );
+        let globalize_physics_cord = move |coord: F64x2| -> F64x2 {
+            F64x2 {
+                x: coord.x,
+                y: win_size[1] * POINTS_TO_METERS - coord.y,
+            }
+        };
+
+        // visible area, in map pixels from the bottom left
+        let min = (cam_loc / map_px_to_meter).floor();
+        let max = (cam_loc + F64x2::from(win_size) * POINTS_TO_METERS) / map_px_to_meter;
+        let min = F64x2::new(min.x.max(0.0), min.y.max(0.0));
+        let max = F64x2::new(max.x.ceil().min(width), max.y.ceil().min(height));
+        if max.x <= min.x || max.y <= min.y {
+            return;
+        }
+
+        Image::new()
+            .src_rect([min.x, height - max.y, max.x - min.x, max.y - min.y])
+            .rect(rectangle_by_points(
+                globalize_physics_cord(min * map_px_to_meter) * METERS_TO_POINTS,
+                globalize_physics_cord(max * map_px_to_meter) * METERS_TO_POINTS,
+            ))
+            .draw(
+                &self.texture,
+                &DrawState::default(),
+                c.transform
+                    .trans(-cam_loc.x * METERS_TO_POINTS, cam_loc.y * METERS_TO_POINTS),
+                gl,
+            );
+    }
+}
@@ -0,0 +1,855 @@
+//! loading of maps made in the [Tiled](https://www.mapeditor.org/) editor, from `.tmx` (XML) or `.tmj` (JSON) files
+//!
+//! every tile in a Tiled map becomes one map pixel. tiles are configured with custom properties on the tileset:
+//!
+//! - `color` (color): what the tile looks like, and the palette entry it uses.
+//!   tiles without one get a generated color
+//! - `effects` (string): RON list of [`TileEffect`]s, e.g. `[Collision("stone")]`
+//! - `conditions` (string): RON list of [`TileEffectCondition`]s, e.g. `[StandingOn]`
+//!
+//! tiles without `effects` use the palette entry for their color from the map directory's palettes, if any.
+//!
+//! tile layers are put on the effect layer, unless they have a `layer` property of `background` or `foreground`.
+//! later layers are drawn over earlier layers of the same kind.
+//!
+//! objects in object layers become [`MapMarker`]s, except for the one named (or with the type) `spawn`,
+//! which sets the spawn point.
+//!
+//! the map's `backgroundcolor` is used as the clear color, and these map properties override the map metadata:
+//! `name`, `map_px_to_meter`, `gravity_x`, `gravity_y`, `linear_drag`, `quadratic_drag`, `par_time`
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::Read,
+    path::Path,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use image::Rgba;
+use serde::Deserialize;
+
+use super::{
+    material,
+    meta::{MapMeta, SpawnPoint},
+    palette, MapImage, MapLayer, MapLayers, MapMarker, TileEffect, TileEffectCondition, WorldMap,
+};
+use crate::{colors::*, vec2::F64x2};
+
+/// the upper bits of a gid store if the tile is flipped, which does not matter here
+const GID_FLAG_MASK: u32 = 0x0fff_ffff;
+
+type Properties = HashMap<String, String>;
+
+/// a Tiled map, with everything not used by limeon stripped out
+#[derive(Debug, Default)]
+struct TiledMap {
+    /// in tiles
+    width: u32,
+    height: u32,
+    /// in pixels, used to convert object locations to tiles
+    tile_width: u32,
+    tile_height: u32,
+    background_color: Option<String>,
+    properties: Properties,
+    /// properties of each tile that has any, by gid
+    tiles: HashMap<u32, Properties>,
+    tile_layers: Vec<TileLayer>,
+    objects: Vec<Object>,
+}
+
+#[derive(Debug)]
+struct TileLayer {
+    name: String,
+    properties: Properties,
+    /// row major, starting at the top left
+    gids: Vec<u32>,
+}
+
+#[derive(Debug)]
+struct Object {
+    name: String,
+    kind: String,
+    /// in pixels, from the top left
+    x: f64,
+    y: f64,
+}
+
+/// loads the Tiled map at `path`. the format is picked based on the file extension
+pub fn load(path: &Path) -> Result<WorldMap> {
+    let src = fs::read_to_string(path)
+        .with_context(|| format!("failed to read Tiled map {}", path.display()))?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let tiled = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("tmx") => parse_tmx(&src, dir),
+        _ => parse_tmj(&src, dir),
+    }
+    .with_context(|| format!("failed to load Tiled map {}", path.display()))?;
+
+    into_world_map(tiled, path)
+        .with_context(|| format!("failed to load Tiled map {}", path.display()))
+}
+
+fn into_world_map(tiled: TiledMap, path: &Path) -> Result<WorldMap> {
+    let mut effect_map = palette::load_for_map(path)?;
+    let mut meta = MapMeta::load_for_map(path)?;
+    apply_map_properties(&mut meta, &tiled)?;
+
+    // assign every used tile a color, and register the effects of tiles that have them
+    let used_gids = tiled
+        .tile_layers
+        .iter()
+        .flat_map(|layer| layer.gids.iter().map(|gid| gid & GID_FLAG_MASK))
+        .filter(|gid| *gid != 0)
+        .collect::<HashSet<u32>>();
+    let mut used_gids = used_gids.into_iter().collect::<Vec<u32>>();
+    used_gids.sort_unstable();
+
+    // tiles with their own color go first, so no generated color can end up the same as one of them
+    let mut tile_colors: HashMap<u32, Rgba<u8>> = HashMap::new();
+    let mut taken_colors = effect_map.keys().copied().collect::<HashSet<Rgba<u8>>>();
+    for gid in &used_gids {
+        if let Some(color) = tiled.tiles.get(gid).and_then(|props| props.get("color")) {
+            let color = parse_tiled_color(color)
+                .ok_or_else(|| anyhow!("tile {}: invalid color {:?}", gid, color))?;
+            taken_colors.insert(color);
+            tile_colors.insert(*gid, color);
+        }
+    }
+    for gid in &used_gids {
+        if !tile_colors.contains_key(gid) {
+            let color = generated_color(*gid, &taken_colors);
+            taken_colors.insert(color);
+            tile_colors.insert(*gid, color);
+        }
+    }
+
+    // colors of tiles that set their own effects, to catch two tiles fighting over one palette entry
+    let mut configured_colors: HashMap<Rgba<u8>, u32> = HashMap::new();
+    for gid in used_gids {
+        let props = tiled.tiles.get(&gid);
+        let prop = |name: &str| props.and_then(|props| props.get(name));
+        let color = tile_colors[&gid];
+
+        if prop("effects").is_some() || prop("conditions").is_some() {
+            if let Some(other) = configured_colors.insert(color, gid) {
+                bail!(
+                    "tiles {} and {} both have the color {:?} but set their own effects",
+                    other,
+                    gid,
+                    color.0
+                );
+            }
+            let effects = match prop("effects") {
+                Some(effects) => ron::from_str::<Vec<TileEffect>>(effects)
+                    .map_err(|e| anyhow!("tile {}: invalid effects {:?}: {}", gid, effects, e))?,
+                None => vec![],
+            };
+            let conditions = match prop("conditions") {
+                Some(conditions) => {
+                    ron::from_str::<Vec<TileEffectCondition>>(conditions).map_err(|e| {
+                        anyhow!("tile {}: invalid conditions {:?}: {}", gid, conditions, e)
+                    })?
+                }
+                None => vec![],
+            };
+            effect_map.insert(color, (effects, conditions));
+        }
+    }
+
+    let mut background = None;
+    let mut effect = MapImage::new(tiled.width, tiled.height);
+    let mut foreground = None;
+    for layer in &tiled.tile_layers {
+        if layer.gids.len() != (tiled.width * tiled.height) as usize {
+            bail!(
+                "layer {:?} has {} tiles, expected {}",
+                layer.name,
+                layer.gids.len(),
+                tiled.width * tiled.height
+            );
+        }
+        let image = match layer_kind(layer)? {
+            MapLayer::Background => {
+                background.get_or_insert_with(|| MapImage::new(tiled.width, tiled.height))
+            }
+            MapLayer::Effect => &mut effect,
+            MapLayer::Foreground => {
+                foreground.get_or_insert_with(|| MapImage::new(tiled.width, tiled.height))
+            }
+        };
+        for (i, gid) in layer.gids.iter().enumerate() {
+            if let Some(color) = tile_colors.get(&(gid & GID_FLAG_MASK)) {
+                image.put_pixel(i as u32 % tiled.width, i as u32 / tiled.width, *color);
+            }
+        }
+    }
+
+    // objects are in pixels from the top left, so convert them to tiles and then meters from the bottom left
+    let object_loc = |object: &Object| {
+        F64x2::new(
+            object.x / tiled.tile_width as f64,
+            tiled.height as f64 - object.y / tiled.tile_height as f64,
+        ) * meta.map_px_to_meter
+    };
+    let mut markers = vec![];
+    let mut spawn = None;
+    for object in &tiled.objects {
+        if object.name == "spawn" || object.kind == "spawn" {
+            if spawn.is_some() {
+                warn!("Tiled map {} has more than one spawn point", path.display());
+            }
+            spawn = Some(object_loc(object));
+        } else {
+            markers.push(MapMarker {
+                name: object.name.clone(),
+                kind: object.kind.clone(),
+                loc: object_loc(object),
+            });
+        }
+    }
+    if let Some(spawn) = spawn {
+        meta.spawn = SpawnPoint::Meters(spawn);
+    }
+
+    let materials = material::load_for_map(path)?;
+    material::check_map(&effect_map, &meta, &materials)
+        .with_context(|| format!("in Tiled map {}", path.display()))?;
+
+    let mut map = WorldMap::new(
+        MapLayers {
+            background,
+            effect,
+            foreground,
+        },
+        effect_map,
+        materials,
+        meta,
+    );
+    map.markers = markers;
+    Ok(map)
+}
+
+fn apply_map_properties(meta: &mut MapMeta, tiled: &TiledMap) -> Result<()> {
+    let float_prop = |name: &str| -> Result<Option<f64>> {
+        tiled
+            .properties
+            .get(name)
+            .map(|value| {
+                value
+                    .parse::<f64>()
+                    .with_context(|| format!("map property {}: invalid number {:?}", name, value))
+            })
+            .transpose()
+    };
+
+    if let Some(name) = tiled.properties.get("name") {
+        meta.name = name.clone();
+    }
+    if let Some(map_px_to_meter) = float_prop("map_px_to_meter")? {
+        if map_px_to_meter <= 0.0 {
+            bail!("map_px_to_meter must be positive, got {}", map_px_to_meter);
+        }
+        meta.map_px_to_meter = map_px_to_meter;
+    }
+    if let Some(x) = float_prop("gravity_x")? {
+        meta.gravity.x = x;
+    }
+    if let Some(y) = float_prop("gravity_y")? {
+        meta.gravity.y = y;
+    }
+    if let Some(linear_drag) = float_prop("linear_drag")? {
+        meta.linear_drag = linear_drag;
+    }
+    if let Some(quadratic_drag) = float_prop("quadratic_drag")? {
+        meta.quadratic_drag = quadratic_drag;
+    }
+    if let Some(par_time) = float_prop("par_time")? {
+        meta.par_time = Some(par_time);
+    }
+    if let Some(color) = &tiled.background_color {
+        let [r, g, b, a] = parse_tiled_color(color)
+            .ok_or_else(|| anyhow!("invalid background color {:?}", color))?
+            .0;
+        meta.clear_color = rgba(r, g, b, a as f32 / 255.0);
+    }
+    Ok(())
+}
+
+fn layer_kind(layer: &TileLayer) -> Result<MapLayer> {
+    match layer.properties.get("layer").map(String::as_str) {
+        None | Some("effect") => Ok(MapLayer::Effect),
+        Some("background") => Ok(MapLayer::Background),
+        Some("foreground") => Ok(MapLayer::Foreground),
+        Some(other) => bail!(
+            "layer {:?}: unknown layer kind {:?}, expected `background`, `effect` or `foreground`",
+            layer.name,
+            other
+        ),
+    }
+}
+
+/// parses a color the way Tiled writes them, `#aarrggbb` or `#rrggbb`
+fn parse_tiled_color(s: &str) -> Option<Rgba<u8>> {
+    let hex = s.trim().trim_start_matches('#');
+    match hex.len() {
+        6 => palette::parse_color(hex),
+        8 => {
+            let Rgba([a, r, g, b]) = palette::parse_color(hex)?;
+            Some(Rgba([r, g, b, a]))
+        }
+        _ => None,
+    }
+}
+
+/// picks an opaque color for a tile without one, that is not used by anything else
+fn generated_color(gid: u32, taken: &HashSet<Rgba<u8>>) -> Rgba<u8> {
+    let mut hash = gid.wrapping_mul(0x9e37_79b1);
+    loop {
+        let [r, g, b, _] = hash.to_le_bytes();
+        let color = Rgba([r, g, b, 255]);
+        if !taken.contains(&color) {
+            return color;
+        }
+        hash = hash.wrapping_add(0x0101_0101);
+    }
+}
+
+/// decodes the tile data of a layer into gids
+fn decode_tile_data(
+    data: &str,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> Result<Vec<u32>> {
+    match encoding {
+        Some("csv") => data
+            .split(',')
+            .map(|gid| {
+                gid.trim()
+                    .parse::<u32>()
+                    .with_context(|| format!("invalid gid {:?}", gid.trim()))
+            })
+            .collect(),
+        Some("base64") => {
+            let raw = base64::decode(data.trim()).context("invalid base64 tile data")?;
+            let bytes = match compression {
+                None | Some("") => raw,
+                Some("zlib") => {
+                    let mut bytes = vec![];
+                    ZlibDecoder::new(&raw[..]).read_to_end(&mut bytes)?;
+                    bytes
+                }
+                Some("gzip") => {
+                    let mut bytes = vec![];
+                    GzDecoder::new(&raw[..]).read_to_end(&mut bytes)?;
+                    bytes
+                }
+                Some(other) => bail!("unsupported tile data compression {:?}", other),
+            };
+            if bytes.len() % 4 != 0 {
+                bail!(
+                    "tile data is {} bytes, which is not a multiple of 4",
+                    bytes.len()
+                );
+            }
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|gid| u32::from_le_bytes([gid[0], gid[1], gid[2], gid[3]]))
+                .collect())
+        }
+        other => bail!("unsupported tile data encoding {:?}", other),
+    }
+}
+
+// -------- TMX (XML) --------
+
+fn parse_tmx(src: &str, dir: &Path) -> Result<TiledMap> {
+    let doc = roxmltree::Document::parse(src)?;
+    let root = doc.root_element();
+    if !root.has_tag_name("map") {
+        bail!(
+            "expected a <map> element, found <{}>",
+            root.tag_name().name()
+        );
+    }
+    if root.attribute("infinite") == Some("1") {
+        bail!("infinite maps are not supported");
+    }
+
+    let mut tiled = TiledMap {
+        width: xml_attr(root, "width")?,
+        height: xml_attr(root, "height")?,
+        tile_width: xml_attr(root, "tilewidth")?,
+        tile_height: xml_attr(root, "tileheight")?,
+        background_color: root.attribute("backgroundcolor").map(str::to_owned),
+        properties: xml_properties(root),
+        ..Default::default()
+    };
+
+    for tileset in root.children().filter(|n| n.has_tag_name("tileset")) {
+        let first_gid: u32 = xml_attr(tileset, "firstgid")?;
+        let tiles = match tileset.attribute("source") {
+            Some(source) => load_external_tileset(&dir.join(source))?,
+            None => xml_tileset_tiles(tileset)?,
+        };
+        tiled
+            .tiles
+            .extend(tiles.into_iter().map(|(id, props)| (first_gid + id, props)));
+    }
+
+    xml_layers(root, &mut tiled)?;
+    Ok(tiled)
+}
+
+/// collects the layers of `parent`, descending into groups
+fn xml_layers(parent: roxmltree::Node, tiled: &mut TiledMap) -> Result<()> {
+    for node in parent.children().filter(|n| n.is_element()) {
+        match node.tag_name().name() {
+            "layer" => {
+                let name = node.attribute("name").unwrap_or_default().to_owned();
+                let data = node
+                    .children()
+                    .find(|n| n.has_tag_name("data"))
+                    .ok_or_else(|| anyhow!("layer {:?} has no data", name))?;
+                let gids = match data.attribute("encoding") {
+                    // plain XML, one <tile> element per tile
+                    None => data
+                        .children()
+                        .filter(|n| n.has_tag_name("tile"))
+                        .map(|tile| {
+                            Ok(tile
+                                .attribute("gid")
+                                .map(str::parse)
+                                .transpose()?
+                                .unwrap_or(0))
+                        })
+                        .collect::<Result<Vec<u32>>>()?,
+                    encoding => decode_tile_data(
+                        data.text().unwrap_or_default(),
+                        encoding,
+                        data.attribute("compression"),
+                    )
+                    .with_context(|| format!("layer {:?}", name))?,
+                };
+                tiled.tile_layers.push(TileLayer {
+                    name,
+                    properties: xml_properties(node),
+                    gids,
+                });
+            }
+            "objectgroup" => {
+                for object in node.children().filter(|n| n.has_tag_name("object")) {
+                    tiled.objects.push(Object {
+                        name: object.attribute("name").unwrap_or_default().to_owned(),
+                        kind: object
+                            .attribute("type")
+                            .or_else(|| object.attribute("class"))
+                            .unwrap_or_default()
+                            .to_owned(),
+                        x: xml_attr(object, "x")?,
+                        y: xml_attr(object, "y")?,
+                    });
+                }
+            }
+            "group" => xml_layers(node, tiled)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// properties of each tile in a `<tileset>`, by local tile id
+fn xml_tileset_tiles(tileset: roxmltree::Node) -> Result<HashMap<u32, Properties>> {
+    tileset
+        .children()
+        .filter(|n| n.has_tag_name("tile"))
+        .map(|tile| Ok((xml_attr(tile, "id")?, xml_properties(tile))))
+        .collect()
+}
+
+fn xml_properties(node: roxmltree::Node) -> Properties {
+    node.children()
+        .filter(|n| n.has_tag_name("properties"))
+        .flat_map(|props| props.children().filter(|n| n.has_tag_name("property")))
+        .filter_map(|prop| {
+            // multiline strings are stored as the element's text instead of the value attribute
+            let value = prop.attribute("value").or_else(|| prop.text())?;
+            Some((prop.attribute("name")?.to_owned(), value.to_owned()))
+        })
+        .collect()
+}
+
+fn xml_attr<T: std::str::FromStr>(node: roxmltree::Node, name: &str) -> Result<T> {
+    let value = node
+        .attribute(name)
+        .ok_or_else(|| anyhow!("<{}> is missing {:?}", node.tag_name().name(), name))?;
+    value.parse().map_err(|_| {
+        anyhow!(
+            "<{}> has an invalid {:?}: {:?}",
+            node.tag_name().name(),
+            name,
+            value
+        )
+    })
+}
+
+// -------- TMJ (JSON) --------
+
+#[derive(Debug, Deserialize)]
+struct TmjMap {
+    width: u32,
+    height: u32,
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default)]
+    infinite: bool,
+    backgroundcolor: Option<String>,
+    #[serde(default)]
+    properties: Vec<TmjProperty>,
+    #[serde(default)]
+    tilesets: Vec<TmjTileset>,
+    #[serde(default)]
+    layers: Vec<TmjLayer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TmjProperty {
+    name: String,
+    value: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct TmjTileset {
+    /// not present in external tilesets
+    #[serde(default)]
+    firstgid: u32,
+    source: Option<String>,
+    #[serde(default)]
+    tiles: Vec<TmjTile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TmjTile {
+    id: u32,
+    #[serde(default)]
+    properties: Vec<TmjProperty>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum TmjLayer {
+    TileLayer {
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        properties: Vec<TmjProperty>,
+        data: TmjData,
+        encoding: Option<String>,
+        compression: Option<String>,
+    },
+    ObjectGroup {
+        #[serde(default)]
+        objects: Vec<TmjObject>,
+    },
+    Group {
+        #[serde(default)]
+        layers: Vec<TmjLayer>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum TmjData {
+    Gids(Vec<u32>),
+    Encoded(String),
+}
+
+#[derive(Debug, Deserialize)]
+struct TmjObject {
+    #[serde(default)]
+    name: String,
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    class: String,
+    x: f64,
+    y: f64,
+}
+
+fn parse_tmj(src: &str, dir: &Path) -> Result<TiledMap> {
+    let map: TmjMap = serde_json::from_str(src)?;
+    if map.infinite {
+        bail!("infinite maps are not supported");
+    }
+
+    let mut tiled = TiledMap {
+        width: map.width,
+        height: map.height,
+        tile_width: map.tilewidth,
+        tile_height: map.tileheight,
+        background_color: map.backgroundcolor,
+        properties: tmj_properties(map.properties),
+        ..Default::default()
+    };
+
+    for tileset in map.tilesets {
+        let first_gid = tileset.firstgid;
+        let tiles = match &tileset.source {
+            Some(source) => load_external_tileset(&dir.join(source))?,
+            None => tmj_tileset_tiles(tileset),
+        };
+        tiled
+            .tiles
+            .extend(tiles.into_iter().map(|(id, props)| (first_gid + id, props)));
+    }
+
+    tmj_layers(map.layers, &mut tiled)?;
+    Ok(tiled)
+}
+
+fn tmj_layers(layers: Vec<TmjLayer>, tiled: &mut TiledMap) -> Result<()> {
+    for layer in layers {
+        match layer {
+            TmjLayer::TileLayer {
+                name,
+                properties,
+                data,
+                encoding,
+                compression,
+            } => {
+                let gids = match data {
+                    TmjData::Gids(gids) => gids,
+                    TmjData::Encoded(data) => decode_tile_data(
+                        &data,
+                        Some(encoding.as_deref().unwrap_or("base64")),
+                        compression.as_deref(),
+                    )
+                    .with_context(|| format!("layer {:?}", name))?,
+                };
+                tiled.tile_layers.push(TileLayer {
+                    name,
+                    properties: tmj_properties(properties),
+                    gids,
+                });
+            }
+            TmjLayer::ObjectGroup { objects } => {
+                tiled
+                    .objects
+                    .extend(objects.into_iter().map(|object| Object {
+                        name: object.name,
+                        kind: if object.kind.is_empty() {
+                            object.class
+                        } else {
+                            object.kind
+                        },
+                        x: object.x,
+                        y: object.y,
+                    }));
+            }
+            TmjLayer::Group { layers } => tmj_layers(layers, tiled)?,
+            TmjLayer::Other => {}
+        }
+    }
+    Ok(())
+}
+
+fn tmj_tileset_tiles(tileset: TmjTileset) -> HashMap<u32, Properties> {
+    tileset
+        .tiles
+        .into_iter()
+        .map(|tile| (tile.id, tmj_properties(tile.properties)))
+        .collect()
+}
+
+fn tmj_properties(properties: Vec<TmjProperty>) -> Properties {
+    properties
+        .into_iter()
+        .map(|prop| {
+            let value = match prop.value {
+                serde_json::Value::String(s) => s,
+                other => other.to_string(),
+            };
+            (prop.name, value)
+        })
+        .collect()
+}
+
+/// loads a `.tsx` or `.tsj` tileset, returning the properties of each tile by local tile id
+fn load_external_tileset(path: &Path) -> Result<HashMap<u32, Properties>> {
+    let src = fs::read_to_string(path)
+        .with_context(|| format!("failed to read tileset {}", path.display()))?;
+    let tiles = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("tsx") => {
+            let doc = roxmltree::Document::parse(&src)?;
+            xml_tileset_tiles(doc.root_element())
+        }
+        _ => serde_json::from_str::<TmjTileset>(&src)
+            .map(tmj_tileset_tiles)
+            .map_err(Into::into),
+    };
+    tiles.with_context(|| format!("failed to load tileset {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::ZlibEncoder, Compression};
+
+    use super::*;
+
+    /// not a real directory, so no palettes, metadata or materials are found next to the test maps
+    const MAP_DIR: &str = "no_such_dir";
+
+    fn zlib_base64(gids: &[u32]) -> String {
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        for gid in gids {
+            encoder.write_all(&gid.to_le_bytes()).unwrap();
+        }
+        base64::encode(encoder.finish().unwrap())
+    }
+
+    #[test]
+    fn colors_are_argb() {
+        assert_eq!(parse_tiled_color("#80ff0000"), Some(Rgba([255, 0, 0, 128])));
+        assert_eq!(parse_tiled_color("#ff8000"), Some(Rgba([255, 128, 0, 255])));
+        assert_eq!(parse_tiled_color("#f80"), None);
+    }
+
+    #[test]
+    fn tile_data_decodes() {
+        assert_eq!(
+            decode_tile_data("\n1,2,\n0,3\n", Some("csv"), None).unwrap(),
+            vec![1, 2, 0, 3]
+        );
+        let gids = [0, 1, 0x8000_0002, 70000];
+        assert_eq!(
+            decode_tile_data(&zlib_base64(&gids), Some("base64"), Some("zlib")).unwrap(),
+            gids
+        );
+        assert!(decode_tile_data("AAAA", Some("base64"), Some("zstd")).is_err());
+        assert!(decode_tile_data("1,x", Some("csv"), None).is_err());
+    }
+
+    #[test]
+    fn generated_colors_avoid_colors_set_by_later_tiles() {
+        // the color tile 1 would get, if tile 2 had not asked for it
+        let [r, g, b, _] = generated_color(1, &HashSet::new()).0;
+        let tiled = TiledMap {
+            width: 2,
+            height: 1,
+            tile_width: 8,
+            tile_height: 8,
+            tiles: HashMap::from([(
+                2,
+                HashMap::from([(
+                    "color".to_string(),
+                    format!("#ff{:02x}{:02x}{:02x}", r, g, b),
+                )]),
+            )]),
+            tile_layers: vec![TileLayer {
+                name: "ground".to_string(),
+                properties: HashMap::new(),
+                gids: vec![1, 2],
+            }],
+            ..Default::default()
+        };
+        let map = into_world_map(tiled, &Path::new(MAP_DIR).join("test.tmj")).unwrap();
+        assert_eq!(*map.layers.effect.get_pixel(1, 0), Rgba([r, g, b, 255]));
+        assert_ne!(
+            map.layers.effect.get_pixel(0, 0),
+            map.layers.effect.get_pixel(1, 0)
+        );
+    }
+
+    #[test]
+    fn tmx_tiles_and_objects_end_up_in_meters() {
+        let src = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="4" height="3" tilewidth="16" tileheight="16" infinite="0">
+ <properties>
+  <property name="map_px_to_meter" type="float" value="0.5"/>
+ </properties>
+ <tileset firstgid="1" name="tiles" tilewidth="16" tileheight="16" tilecount="2" columns="2">
+  <tile id="1">
+   <properties>
+    <property name="color" type="color" value="#ff00ff00"/>
+    <property name="effects" value="[Wind((0.0, 1.0))]"/>
+    <property name="conditions" value="[InsideOf]"/>
+   </properties>
+  </tile>
+ </tileset>
+ <layer id="1" name="ground" width="4" height="3">
+  <data encoding="csv">
+0,0,0,0,
+0,0,0,0,
+2,2,2147483650,1
+</data>
+ </layer>
+ <objectgroup id="2" name="objects">
+  <object id="1" name="spawn" x="32" y="16"/>
+  <object id="2" name="box" type="crate" x="8" y="48"/>
+ </objectgroup>
+</map>"##;
+        let path = Path::new(MAP_DIR).join("test.tmx");
+        let map = into_world_map(parse_tmx(src, Path::new(MAP_DIR)).unwrap(), &path).unwrap();
+
+        let green = Rgba([0, 255, 0, 255]);
+        // the bottom row, with the flipped tile in the third column
+        for x in 0..3 {
+            assert_eq!(*map.layers.effect.get_pixel(x, 2), green);
+        }
+        assert_ne!(*map.layers.effect.get_pixel(3, 2), green);
+        assert_eq!(
+            map.effect_map[&green],
+            (
+                vec![TileEffect::Wind(F64x2::new(0.0, 1.0))],
+                vec![TileEffectCondition::InsideOf]
+            )
+        );
+
+        assert_eq!(map.spawn_loc(), F64x2::new(1.0, 1.0));
+        assert_eq!(
+            map.markers,
+            vec![MapMarker {
+                name: "box".to_string(),
+                kind: "crate".to_string(),
+                loc: F64x2::new(0.25, 0.0),
+            }]
+        );
+    }
+
+    #[test]
+    fn tmj_tiles_and_objects_end_up_in_meters() {
+        let src = format!(
+            r##"{{
+    "width": 2, "height": 2, "tilewidth": 8, "tileheight": 8, "infinite": false,
+    "properties": [{{ "name": "map_px_to_meter", "type": "float", "value": 0.25 }}],
+    "tilesets": [{{
+        "firstgid": 1,
+        "tiles": [{{ "id": 0, "properties": [{{ "name": "color", "type": "color", "value": "#ff0000ff" }}] }}]
+    }}],
+    "layers": [
+        {{ "type": "tilelayer", "name": "ground", "data": "{}", "encoding": "base64", "compression": "zlib" }},
+        {{ "type": "objectgroup", "objects": [
+            {{ "name": "spawn", "x": 4, "y": 12 }},
+            {{ "name": "", "class": "crate", "x": 8, "y": 0 }}
+        ] }}
+    ]
+}}"##,
+            zlib_base64(&[0, 0, 1, 1])
+        );
+        let path = Path::new(MAP_DIR).join("test.tmj");
+        let map = into_world_map(parse_tmj(&src, Path::new(MAP_DIR)).unwrap(), &path).unwrap();
+
+        let blue = Rgba([0, 0, 255, 255]);
+        assert_eq!(*map.layers.effect.get_pixel(0, 0), Rgba([0, 0, 0, 0]));
+        assert_eq!(*map.layers.effect.get_pixel(1, 1), blue);
+        assert_eq!(map.spawn_loc(), F64x2::new(0.125, 0.125));
+        assert_eq!(map.markers.len(), 1);
+        assert_eq!(map.markers[0].kind, "crate");
+        assert_eq!(map.markers[0].loc, F64x2::new(0.25, 0.5));
+    }
+}